@@ -34,6 +34,7 @@ undefine CC
 undefine CXX
 undefine AS
 undefine LD
+undefine OBJCOPY
 
 # RISC-V toolchains, irrespective of their name-tuple, can compile for
 # essentially any target. Thus, try a few known names and choose the
@@ -106,6 +107,7 @@ ifeq ($(OG_ARCH_FAMILY),rv32i)
   CXX             ?= $(TOOLCHAIN_rv32i)g++
   AS              ?= $(TOOLCHAIN_rv32i)as
   LD              ?= $(TOOLCHAIN_rv32i)ld
+  OBJCOPY         ?= $(TOOLCHAIN_rv32i)objcopy
 
   # Determine the version of the RISC-V compiler. This is used to select the
   # version of the libgcc library that is compatible.
@@ -153,6 +155,7 @@ ifeq ($(OG_ARCH_FAMILY),rv32i)
   CFLAGS          := \
     -march=$(OG_RV32I_MARCH) -mabi=ilp32 -mcmodel=medlow \
     -std=c99 -nodefaultlibs -nostdlib -ffreestanding \
+    -I$(OG_TOCK_BASEDIR)/omniglot_c_rt \
     -isystem=$(NEWLIB_BASE_DIR)/$(NEWLIB_INC) \
     -isystem $(LIBCPP_BASE_DIR)/$(LIBCPP_INC)/c++/$(LIBCPP_VERSION_rv32) \
     -isystem $(LIBCPP_BASE_DIR)/$(LIBCPP_INC)/c++/$(LIBCPP_VERSION_rv32)/riscv64-unknown-elf \
@@ -175,6 +178,7 @@ else ifeq ($(OG_ARCH),cortexm4)
   CXX             ?= $(TOOLCHAIN_cortexm)g++
   AS              ?= $(TOOLCHAIN_cortexm)as
   LD              ?= $(TOOLCHAIN_cortexm)ld
+  OBJCOPY         ?= $(TOOLCHAIN_cortexm)objcopy
 
   ARCH            := cortex-m4
   CFLAGS          := -std=gnu11
@@ -11,6 +11,27 @@ COBJ      := $(addprefix $(BUILDDIR)/, $(addsuffix .o, $(subst $(SRCDIR)/,,$(CSR
 ASSRC     := $(foreach x, $(SRCDIR), $(wildcard $(addprefix $(x)/*,.S))) $(INIT_S)
 ASOBJ     := $(addprefix $(BUILDDIR)/, $(addsuffix .S.o, $(notdir $(basename $(ASSRC)))))
 
+# Function table of the library, generated from the names of the functions in
+# OG_FNTAB, in the order of the Rust bindings' symbol table. This defines
+# `omniglot_fntab` along with the `omniglot_fntab_names` table that the kernel
+# requires to verify symbols. Libraries that define their own function table
+# instead must also define a matching `omniglot_fntab_names`.
+ifneq (,$(OG_FNTAB))
+  FNTAB_OBJ := $(BUILDDIR)/omniglot_fntab.S.o
+endif
+
+# Hash over the signatures and layouts of the library's interface, as listed
+# in OG_INTERFACE_SPEC and declared in the interface header OG_INTERFACE_H (see
+# omniglot_interface.c). The kernel compares this against the hash computed
+# from the Rust bindings through `omniglot_tock::interface_hash!`. Zero if no
+# interface is specified.
+ifneq (,$(OG_INTERFACE_SPEC))
+  ifeq (,$(OG_INTERFACE_H))
+    $(error Requires OG_INTERFACE_H to be set along with OG_INTERFACE_SPEC)
+  endif
+  INTERFACE_HASH_FILE := $(BUILDDIR)/omniglot_interface.hash
+endif
+
 .PHONY: all
 all: $(BUILDDIR)/$(OG_TARGET)_$(OG_BIN_NAME).tab
 
@@ -34,16 +55,38 @@ $(BUILDDIR)/%.S.o: %.S*
 	mkdir -p $(BUILDDIR)
 	$(AS) $(ASFLAGS) -o $@ -g -c $<
 
+# Regenerated on every build, but only replaced if OG_FNTAB changed:
+.PHONY: FORCE
+$(BUILDDIR)/omniglot_fntab.S: $(OG_TOCK_BASEDIR)/omniglot_c_rt/gen_fntab.sh FORCE
+	mkdir -p $(BUILDDIR)
+	sh $< $(OG_FNTAB) > $@.tmp
+	cmp -s $@.tmp $@ && rm $@.tmp || mv $@.tmp $@
+
+$(BUILDDIR)/omniglot_fntab.S.o: $(BUILDDIR)/omniglot_fntab.S
+	$(AS) $(ASFLAGS) -o $@ -g -c $<
+
+# The probe is compiled with the library's CFLAGS, such that it computes the
+# layouts of the library's build:
+$(BUILDDIR)/omniglot_interface.hash: \
+    $(OG_TOCK_BASEDIR)/omniglot_c_rt/interface_hash.sh \
+    $(OG_TOCK_BASEDIR)/omniglot_c_rt/omniglot_interface.c \
+    $(OG_INTERFACE_SPEC) $(OG_INTERFACE_H)
+	mkdir -p $(BUILDDIR)
+	CC="$(CC)" OBJCOPY="$(OBJCOPY)" sh $< \
+	  $(OG_INTERFACE_H) $(OG_INTERFACE_SPEC) $(BUILDDIR) $(CFLAGS) > $@.tmp
+	mv $@.tmp $@
+
 $(BUILDDIR)/$(OG_TARGET)_$(OG_BIN_NAME).elf: \
-    $(COBJ) $(ASOBJ) $(BUILDDIR)/sys.o \
+    $(COBJ) $(ASOBJ) $(FNTAB_OBJ) $(BUILDDIR)/sys.o \
     $(OG_SYSTEM_LIBS) \
     $(OG_LAYOUT_LD) \
     $(OG_TOCK_BASEDIR)/omniglot_c_rt/omniglot_layout.ld \
-    $(OG_LINK_OBJ)
+    $(OG_LINK_OBJ) $(INTERFACE_HASH_FILE)
 	mkdir -p $(BUILDDIR)
 	OG_TOCK_BASEDIR=$(OG_TOCK_BASEDIR) envsubst '$$OG_TOCK_BASEDIR' \
 	  < $(OG_LAYOUT_LD) > $(BUILDDIR)/omniglot_layout.ld
-	$(LD) --no-relax -o $@ $(COBJ) $(ASOBJ) $(BUILDDIR)/sys.o $(OG_LINK_OBJ) $(OG_SYSTEM_LIBS) -T$(BUILDDIR)/omniglot_layout.ld $(LDFLAGS)
+	$(LD) --no-relax -o $@ $(COBJ) $(ASOBJ) $(FNTAB_OBJ) $(BUILDDIR)/sys.o $(OG_LINK_OBJ) $(OG_SYSTEM_LIBS) -T$(BUILDDIR)/omniglot_layout.ld \
+	  --defsym=omniglot_interface_hash=$(if $(INTERFACE_HASH_FILE),$$(cat $(INTERFACE_HASH_FILE)),0) $(LDFLAGS)
 
 $(BUILDDIR)/$(OG_TARGET)_$(OG_BIN_NAME).tab: $(BUILDDIR)/$(OG_TARGET)_$(OG_BIN_NAME).elf
 	mkdir -p $(BUILDDIR)
@@ -0,0 +1,51 @@
+#!/bin/sh
+# Generate the function table of an Omniglot library, along with the table of
+# its function names, from the names of the functions passed as arguments.
+#
+# The output is an assembly file defining `omniglot_fntab`, an array of
+# pointers to the functions, and `omniglot_fntab_names`, an array of pointers
+# to their NUL-terminated names, in the order given. `omniglot_fntab_end`
+# marks the end of the function table, from which `omniglot_layout.ld`
+# derives its length. The kernel compares the names against the symbols
+# requested by the Rust bindings when resolving them.
+#
+# Usage: gen_fntab.sh FUNCTION... > omniglot_fntab.S
+
+set -e
+
+for fn in "$@"; do
+    case "$fn" in
+        "" | [0-9]* | *[!A-Za-z0-9_]*)
+            echo "gen_fntab.sh: invalid function name: '$fn'" >&2
+            exit 1
+            ;;
+    esac
+done
+
+echo "/* Generated by gen_fntab.sh, do not edit. */"
+echo
+echo ".section .omniglot_hdr, \"a\""
+echo ".balign 4"
+echo ".globl omniglot_fntab"
+echo "omniglot_fntab:"
+for fn in "$@"; do
+    echo "        .4byte $fn"
+done
+echo ".globl omniglot_fntab_end"
+echo "omniglot_fntab_end:"
+echo
+echo ".globl omniglot_fntab_names"
+echo "omniglot_fntab_names:"
+idx=0
+for fn in "$@"; do
+    echo "        .4byte .Lomniglot_fntab_name_$idx"
+    idx=$((idx + 1))
+done
+echo
+echo ".section .rodata.omniglot_fntab_names, \"a\""
+idx=0
+for fn in "$@"; do
+    echo ".Lomniglot_fntab_name_$idx:"
+    echo "        .asciz \"$fn\""
+    idx=$((idx + 1))
+done
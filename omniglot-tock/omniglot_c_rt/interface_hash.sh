@@ -0,0 +1,27 @@
+#!/bin/sh
+# Compute the interface hash of an Omniglot library, which OmniglotTBF.mk
+# embeds into its binary, and which `omniglot_tock::interface_hash!` computes
+# from the Rust bindings.
+#
+# omniglot_interface.c is compiled with the library's CFLAGS, such that it
+# computes the layouts of the library's build, and with its interface header
+# included. cksum hashes the resulting words, as
+# `omniglot_tock::binary::interface_hash` does. The compiler and objcopy are
+# taken from $CC and $OBJCOPY.
+#
+# Usage: interface_hash.sh HEADER SPEC BUILDDIR [CFLAGS...] > hash
+
+set -e
+
+header=$(realpath "$1")
+spec=$(realpath "$2")
+builddir="$3"
+shift 3
+
+${CC:-cc} "$@" -o "$builddir/omniglot_interface.o" -c \
+    -include "$header" \
+    "-DOG_INTERFACE_SPEC=\"$spec\"" \
+    "$(dirname "$0")/omniglot_interface.c"
+${OBJCOPY:-objcopy} -O binary -j .omniglot_interface \
+    "$builddir/omniglot_interface.o" "$builddir/omniglot_interface.bin"
+cksum < "$builddir/omniglot_interface.bin" | cut -d' ' -f1
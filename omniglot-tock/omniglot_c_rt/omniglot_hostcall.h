@@ -0,0 +1,29 @@
+#ifndef OMNIGLOT_HOSTCALL_H
+#define OMNIGLOT_HOSTCALL_H
+
+#include <stdint.h>
+
+/* Returned in ret0 when the kernel does not provide a host call number to this
+ * library. Keep in sync with `HOST_CALL_ERR_NOSYS` in the Omniglot runtime. */
+#define OMNIGLOT_HOSTCALL_ENOSYS ((uint32_t) -38)
+
+/* Host call numbers of services provided by the Omniglot runtime. Keep in sync
+ * with the `HOST_CALL_*` constants of the runtime. */
+#define OMNIGLOT_HOSTCALL_CONSOLE_WRITE 1
+
+/* Values returned by the kernel, in the first two argument registers of the
+ * architecture (a0 and a1 on RISC-V). */
+struct omniglot_hostcall_ret {
+  uint32_t ret0;
+  uint32_t ret1;
+};
+
+/* Issue host call `num` with up to six arguments. Unused arguments should be
+ * passed as 0. Implemented by the startup code of each architecture supported
+ * by an Omniglot runtime, currently init_riscv32.S (through `ecall`). */
+struct omniglot_hostcall_ret omniglot_hostcall(
+  uint32_t arg0, uint32_t arg1, uint32_t arg2,
+  uint32_t arg3, uint32_t arg4, uint32_t arg5,
+  uint32_t num);
+
+#endif // OMNIGLOT_HOSTCALL_H
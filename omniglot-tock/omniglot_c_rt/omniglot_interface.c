@@ -0,0 +1,95 @@
+/* Probe computing the canonical form of a library's interface, from which
+ * OmniglotTBF.mk derives the interface hash embedded into its binary.
+ *
+ * This is compiled with the library's CFLAGS, including its interface header
+ * (OG_INTERFACE_H), such that all layouts are those of the library's build,
+ * e.g., with -fshort-enums or packed structs. OG_INTERFACE_SPEC names a file
+ * listing the interface, one item per line:
+ *
+ *     OG_INTERFACE_FN(ret, name, args...)    function, ret may be void
+ *     OG_INTERFACE_STRUCT(type, fields...)   struct or union, no bitfields
+ *     OG_INTERFACE_TYPE(type)                any other type, e.g., an enum
+ *
+ * Every function is checked against its declaration in the interface header.
+ * The canonical form is an array of 32-bit words, placed into the
+ * `.omniglot_interface` section, which consists of the following records:
+ *
+ *     function  1, nargs, ret size, ret align, (arg size, arg align)...
+ *     struct    2, size, align, nfields, (field offset, field size)...
+ *     type      3, size, align
+ *
+ * `void` has a size and alignment of zero. The Rust bindings compute the
+ * same words through `omniglot_tock::interface_hash!`, which must list the
+ * same items in the same order.
+ */
+
+#include <stddef.h>
+#include <stdint.h>
+
+#define OG_CAT_(a, b) a##b
+#define OG_CAT(a, b) OG_CAT_(a, b)
+
+/* Number of variable arguments, where the first argument is ignored: */
+#define OG_NARGS(_, ...) OG_NARGS_(_, ##__VA_ARGS__, 8, 7, 6, 5, 4, 3, 2, 1, 0)
+#define OG_NARGS_(_, a1, a2, a3, a4, a5, a6, a7, a8, n, ...) n
+
+/* Apply m(a, x) to every variable argument x: */
+#define OG_FOR_EACH(m, a, ...) \
+  OG_CAT(OG_FOR_EACH_, OG_NARGS(_, ##__VA_ARGS__))(m, a, ##__VA_ARGS__)
+#define OG_FOR_EACH_0(m, a)
+#define OG_FOR_EACH_1(m, a, x) m(a, x)
+#define OG_FOR_EACH_2(m, a, x, ...) m(a, x) OG_FOR_EACH_1(m, a, __VA_ARGS__)
+#define OG_FOR_EACH_3(m, a, x, ...) m(a, x) OG_FOR_EACH_2(m, a, __VA_ARGS__)
+#define OG_FOR_EACH_4(m, a, x, ...) m(a, x) OG_FOR_EACH_3(m, a, __VA_ARGS__)
+#define OG_FOR_EACH_5(m, a, x, ...) m(a, x) OG_FOR_EACH_4(m, a, __VA_ARGS__)
+#define OG_FOR_EACH_6(m, a, x, ...) m(a, x) OG_FOR_EACH_5(m, a, __VA_ARGS__)
+#define OG_FOR_EACH_7(m, a, x, ...) m(a, x) OG_FOR_EACH_6(m, a, __VA_ARGS__)
+#define OG_FOR_EACH_8(m, a, x, ...) m(a, x) OG_FOR_EACH_7(m, a, __VA_ARGS__)
+
+/* Parameter list of a function type, `void` if there are no arguments: */
+#define OG_PARAMS(...) OG_CAT(OG_PARAMS_, OG_NARGS(_, ##__VA_ARGS__))(__VA_ARGS__)
+#define OG_PARAMS_0(...) void
+#define OG_PARAMS_1(...) __VA_ARGS__
+#define OG_PARAMS_2(...) __VA_ARGS__
+#define OG_PARAMS_3(...) __VA_ARGS__
+#define OG_PARAMS_4(...) __VA_ARGS__
+#define OG_PARAMS_5(...) __VA_ARGS__
+#define OG_PARAMS_6(...) __VA_ARGS__
+#define OG_PARAMS_7(...) __VA_ARGS__
+#define OG_PARAMS_8(...) __VA_ARGS__
+
+#define OG_IS_VOID(t) __builtin_types_compatible_p(t, void)
+#define OG_SIZE(t) ((uint32_t) (OG_IS_VOID(t) ? 0 : sizeof(t)))
+#define OG_ALIGN(t) ((uint32_t) (OG_IS_VOID(t) ? 0 : __alignof__(t)))
+
+/* First pass: check the functions against the interface header. */
+#define OG_INTERFACE_FN(ret, name, ...)                                       \
+  _Static_assert(                                                             \
+    __builtin_types_compatible_p(__typeof__(name), ret(OG_PARAMS(__VA_ARGS__))), \
+    "signature of " #name " does not match OG_INTERFACE_SPEC");
+#define OG_INTERFACE_STRUCT(type, ...)
+#define OG_INTERFACE_TYPE(type)
+
+#include OG_INTERFACE_SPEC
+
+#undef OG_INTERFACE_FN
+#undef OG_INTERFACE_STRUCT
+#undef OG_INTERFACE_TYPE
+
+/* Second pass: emit the canonical form. */
+#define OG_ARG(_, t) OG_SIZE(t), OG_ALIGN(t),
+#define OG_FIELD(type, field) \
+  (uint32_t) offsetof(type, field), (uint32_t) sizeof(((type *) 0)->field),
+
+#define OG_INTERFACE_FN(ret, name, ...)                  \
+  1, OG_NARGS(_, ##__VA_ARGS__), OG_SIZE(ret), OG_ALIGN(ret), \
+  OG_FOR_EACH(OG_ARG, _, ##__VA_ARGS__)
+#define OG_INTERFACE_STRUCT(type, ...)                         \
+  2, OG_SIZE(type), OG_ALIGN(type), OG_NARGS(_, ##__VA_ARGS__), \
+  OG_FOR_EACH(OG_FIELD, type, ##__VA_ARGS__)
+#define OG_INTERFACE_TYPE(type) 3, OG_SIZE(type), OG_ALIGN(type),
+
+__attribute__((section(".omniglot_interface"), used))
+const uint32_t omniglot_interface[] = {
+#include OG_INTERFACE_SPEC
+};
@@ -5,6 +5,8 @@
 #include <sys/stat.h>
 #include <sys/types.h>
 
+#include "omniglot_hostcall.h"
+
 // XXX Suppress unused parameter warnings for this file as the implementations
 // are currently all just stubs
 #pragma GCC diagnostic ignored "-Wunused-parameter"
@@ -61,11 +63,41 @@ int _getpid(void) {
 int _kill(pid_t pid, int sig) {
   return -1;
 }
+// Heap region, as defined by the linker script (omniglot_layout.ld). It is
+// empty unless the library defines HEAP_SIZE.
+extern char _heap_start[];
+extern char _heap_end[];
+
+// Current program break. Its address is part of the runtime header, such that
+// the kernel can determine the heap usage. Placed in .data, so that it is
+// reset whenever the library is re-initialized:
+char* omniglot_heap_brk = _heap_start;
+
 caddr_t _sbrk(int incr) {
-  /* return (caddr_t) -1; */
-  intptr_t nullptr = 0;
-  *((size_t *) nullptr) = 0;
+  char* brk = omniglot_heap_brk;
+  if ((incr > 0 && incr > _heap_end - brk)
+      || (incr < 0 && -incr > brk - _heap_start)) {
+    errno = ENOMEM;
+    return (caddr_t) -1;
+  }
+  omniglot_heap_brk = brk + incr;
+  return (caddr_t) brk;
 }
-int _write(__attribute__ ((unused)) int fd, __attribute__ ((unused)) const void* buf, uint32_t count) {
-    return count;
+int _write(int fd, const void* buf, uint32_t count) {
+  // Route stdout and stderr to the kernel console, if it provides the
+  // console write host call. Discard all other output:
+  if (fd == 1 || fd == 2) {
+    struct omniglot_hostcall_ret ret = omniglot_hostcall(
+      (uint32_t) fd, (uint32_t) buf, count, 0, 0, 0,
+      OMNIGLOT_HOSTCALL_CONSOLE_WRITE);
+    if (ret.ret0 != OMNIGLOT_HOSTCALL_ENOSYS) {
+      // Host calls return errors as negated errno values:
+      if ((int32_t) ret.ret0 < 0) {
+        errno = -(int32_t) ret.ret0;
+        return -1;
+      }
+      return (int) ret.ret0;
+    }
+  }
+  return count;
 }
@@ -0,0 +1,21 @@
+/* Interface header of the `interface_hash_matches_c_probe` test in
+ * src/binary.rs. See vectors.sh. */
+
+#include <stdint.h>
+
+enum og_mode { OG_A, OG_B };
+
+struct og_point {
+  uint8_t tag;
+  uint32_t x;
+  uint16_t y;
+};
+
+struct __attribute__((packed)) og_packed {
+  uint8_t a;
+  uint32_t b;
+};
+
+uint32_t og_add(uint32_t a, uint32_t b);
+void og_init(void);
+void og_set_mode(enum og_mode mode, struct og_point p);
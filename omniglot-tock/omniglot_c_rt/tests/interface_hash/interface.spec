@@ -0,0 +1,6 @@
+OG_INTERFACE_FN(uint32_t, og_add, uint32_t, uint32_t)
+OG_INTERFACE_FN(void, og_init)
+OG_INTERFACE_FN(void, og_set_mode, enum og_mode, struct og_point)
+OG_INTERFACE_STRUCT(struct og_point, tag, x, y)
+OG_INTERFACE_STRUCT(struct og_packed, a, b)
+OG_INTERFACE_TYPE(enum og_mode)
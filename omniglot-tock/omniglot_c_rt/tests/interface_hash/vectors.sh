@@ -0,0 +1,26 @@
+#!/bin/sh
+# Compute the interface hashes expected by the `interface_hash_matches_c_probe`
+# test in src/binary.rs, for interface.h and interface.spec, through the same
+# script that OmniglotTBF.mk uses.
+#
+# By default, this uses the host compiler with -m32, whose layouts match
+# those of rv32i for this interface. Set CC and OG_TEST_CFLAGS to use another
+# compiler, e.g., CC=riscv64-unknown-elf-gcc OG_TEST_CFLAGS="-march=rv32i
+# -mabi=ilp32".
+#
+# Usage: vectors.sh
+
+set -e
+
+dir=$(dirname "$0")
+builddir=$(mktemp -d)
+trap 'rm -rf "$builddir"' EXIT
+
+interface_hash() {
+    sh "$dir/../../interface_hash.sh" \
+        "$dir/interface.h" "$dir/interface.spec" "$builddir" \
+        ${OG_TEST_CFLAGS:--m32} -ffreestanding -std=c99 "$@"
+}
+
+echo "HASH: $(interface_hash)"
+echo "SHORT_ENUMS_HASH: $(interface_hash -fshort-enums)"
@@ -0,0 +1,260 @@
+//! Policies determining how a runtime reacts to faults in foreign code,
+//! modeled on Tock's `ProcessFaultPolicy`.
+
+use core::cell::Cell;
+
+use kernel::hil::time::{ConvertTicks, Ticks, Time};
+
+/// Action to take when a foreign library faults.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OmniglotFaultAction {
+    /// Panic the kernel, reporting the fault.
+    Panic,
+
+    /// Stop the library. All subsequent invocations return an error. The
+    /// library may still be re-initialized explicitly, through the runtime's
+    /// `reset` method.
+    Stop,
+
+    /// Re-initialize the library before the next invocation, once the
+    /// policy's `may_restart` method permits it.
+    Restart,
+}
+
+/// Policy consulted by an Omniglot runtime whenever foreign code faults.
+pub trait OmniglotFaultPolicy {
+    /// Decide how to handle a fault. `restart_count` is the number of times
+    /// that the runtime has been restarted by its fault policy so far.
+    fn action(&self, restart_count: usize) -> OmniglotFaultAction;
+
+    /// Called before performing a restart requested through
+    /// `OmniglotFaultAction::Restart`. Returning `false` defers the restart
+    /// and refuses the current invocation.
+    fn may_restart(&self, _restart_count: usize) -> bool {
+        true
+    }
+}
+
+/// Panic the kernel whenever a library faults.
+pub struct PanicFaultPolicy {}
+
+impl OmniglotFaultPolicy for PanicFaultPolicy {
+    fn action(&self, _: usize) -> OmniglotFaultAction {
+        OmniglotFaultAction::Panic
+    }
+}
+
+/// Stop a library when it faults.
+pub struct StopFaultPolicy {}
+
+impl OmniglotFaultPolicy for StopFaultPolicy {
+    fn action(&self, _: usize) -> OmniglotFaultAction {
+        OmniglotFaultAction::Stop
+    }
+}
+
+/// Always restart a library when it faults.
+pub struct RestartFaultPolicy {}
+
+impl OmniglotFaultPolicy for RestartFaultPolicy {
+    fn action(&self, _: usize) -> OmniglotFaultAction {
+        OmniglotFaultAction::Restart
+    }
+}
+
+/// Restart a library when it faults, until it has been restarted `threshold`
+/// times. After that, stop it.
+pub struct ThresholdRestartFaultPolicy {
+    threshold: usize,
+}
+
+impl ThresholdRestartFaultPolicy {
+    pub const fn new(threshold: usize) -> ThresholdRestartFaultPolicy {
+        ThresholdRestartFaultPolicy { threshold }
+    }
+}
+
+impl OmniglotFaultPolicy for ThresholdRestartFaultPolicy {
+    fn action(&self, restart_count: usize) -> OmniglotFaultAction {
+        if restart_count < self.threshold {
+            OmniglotFaultAction::Restart
+        } else {
+            OmniglotFaultAction::Stop
+        }
+    }
+}
+
+/// Restart a library when it faults, until it has been restarted `threshold`
+/// times. After that, panic the kernel.
+pub struct ThresholdRestartThenPanicFaultPolicy {
+    threshold: usize,
+}
+
+impl ThresholdRestartThenPanicFaultPolicy {
+    pub const fn new(threshold: usize) -> ThresholdRestartThenPanicFaultPolicy {
+        ThresholdRestartThenPanicFaultPolicy { threshold }
+    }
+}
+
+impl OmniglotFaultPolicy for ThresholdRestartThenPanicFaultPolicy {
+    fn action(&self, restart_count: usize) -> OmniglotFaultAction {
+        if restart_count < self.threshold {
+            OmniglotFaultAction::Restart
+        } else {
+            OmniglotFaultAction::Panic
+        }
+    }
+}
+
+/// Restart a library when it faults, until it has been restarted `threshold`
+/// times, after which it is stopped. Restarts are delayed by an exponential
+/// backoff: the n-th restart happens no earlier than `base_backoff_ms * 2^n`
+/// milliseconds after the fault. Invocations within this period are refused.
+pub struct BackoffRestartFaultPolicy<'a, T: Time> {
+    time: &'a T,
+    base_backoff_ms: u32,
+    threshold: usize,
+    fault_time: Cell<Option<T::Ticks>>,
+}
+
+impl<'a, T: Time> BackoffRestartFaultPolicy<'a, T> {
+    pub fn new(time: &'a T, base_backoff_ms: u32, threshold: usize) -> Self {
+        BackoffRestartFaultPolicy {
+            time,
+            base_backoff_ms,
+            threshold,
+            fault_time: Cell::new(None),
+        }
+    }
+}
+
+impl<T: Time> OmniglotFaultPolicy for BackoffRestartFaultPolicy<'_, T> {
+    fn action(&self, restart_count: usize) -> OmniglotFaultAction {
+        if restart_count < self.threshold {
+            self.fault_time.set(Some(self.time.now()));
+            OmniglotFaultAction::Restart
+        } else {
+            OmniglotFaultAction::Stop
+        }
+    }
+
+    fn may_restart(&self, restart_count: usize) -> bool {
+        let Some(fault_time) = self.fault_time.get() else {
+            return true;
+        };
+
+        // Cap the shift to avoid overflowing the backoff period:
+        let backoff_ms = self
+            .base_backoff_ms
+            .saturating_mul(1 << core::cmp::min(restart_count, 16));
+
+        self.time.now().wrapping_sub(fault_time) >= self.time.ticks_from_ms(backoff_ms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+
+    use kernel::hil::time::{Freq1KHz, Ticks32, Time};
+
+    use super::*;
+
+    // Time with a resolution of one millisecond, advanced manually:
+    struct MockTime(Cell<u32>);
+
+    impl Time for MockTime {
+        type Frequency = Freq1KHz;
+        type Ticks = Ticks32;
+
+        fn now(&self) -> Ticks32 {
+            self.0.get().into()
+        }
+    }
+
+    #[test]
+    fn fixed_policies() {
+        for restart_count in [0, 1, usize::MAX] {
+            assert_eq!(
+                PanicFaultPolicy {}.action(restart_count),
+                OmniglotFaultAction::Panic
+            );
+            assert_eq!(
+                StopFaultPolicy {}.action(restart_count),
+                OmniglotFaultAction::Stop
+            );
+            assert_eq!(
+                RestartFaultPolicy {}.action(restart_count),
+                OmniglotFaultAction::Restart
+            );
+            assert!(RestartFaultPolicy {}.may_restart(restart_count));
+        }
+    }
+
+    #[test]
+    fn threshold_restart_then_stop() {
+        let policy = ThresholdRestartFaultPolicy::new(2);
+        assert_eq!(policy.action(0), OmniglotFaultAction::Restart);
+        assert_eq!(policy.action(1), OmniglotFaultAction::Restart);
+        assert_eq!(policy.action(2), OmniglotFaultAction::Stop);
+        assert!(policy.may_restart(1));
+
+        let policy = ThresholdRestartFaultPolicy::new(0);
+        assert_eq!(policy.action(0), OmniglotFaultAction::Stop);
+    }
+
+    #[test]
+    fn threshold_restart_then_panic() {
+        let policy = ThresholdRestartThenPanicFaultPolicy::new(2);
+        assert_eq!(policy.action(0), OmniglotFaultAction::Restart);
+        assert_eq!(policy.action(1), OmniglotFaultAction::Restart);
+        assert_eq!(policy.action(2), OmniglotFaultAction::Panic);
+    }
+
+    #[test]
+    fn backoff_delays_restarts() {
+        let time = MockTime(Cell::new(100));
+        let policy = BackoffRestartFaultPolicy::new(&time, 10, 3);
+
+        // Nothing faulted yet:
+        assert!(policy.may_restart(0));
+
+        // First restart after 10ms:
+        assert_eq!(policy.action(0), OmniglotFaultAction::Restart);
+        time.0.set(109);
+        assert!(!policy.may_restart(0));
+        time.0.set(110);
+        assert!(policy.may_restart(0));
+
+        // Second restart after 20ms, measured from the second fault:
+        time.0.set(200);
+        assert_eq!(policy.action(1), OmniglotFaultAction::Restart);
+        time.0.set(219);
+        assert!(!policy.may_restart(1));
+        time.0.set(220);
+        assert!(policy.may_restart(1));
+
+        // Stop once the threshold is reached:
+        assert_eq!(policy.action(2), OmniglotFaultAction::Restart);
+        assert_eq!(policy.action(3), OmniglotFaultAction::Stop);
+    }
+
+    #[test]
+    fn backoff_caps_shift() {
+        let time = MockTime(Cell::new(0));
+        let policy = BackoffRestartFaultPolicy::new(&time, 1, usize::MAX);
+
+        // The shift is capped at 16, even for large restart counts:
+        assert_eq!(policy.action(40), OmniglotFaultAction::Restart);
+        time.0.set(65535);
+        assert!(!policy.may_restart(40));
+        time.0.set(65536);
+        assert!(policy.may_restart(40));
+
+        // The backoff period saturates instead of overflowing:
+        let policy = BackoffRestartFaultPolicy::new(&time, u32::MAX, usize::MAX);
+        assert_eq!(policy.action(1), OmniglotFaultAction::Restart);
+        time.0.set(65536 + 1000);
+        assert!(!policy.may_restart(1));
+    }
+}
@@ -0,0 +1,39 @@
+//! Hooks to service kernel interrupts while foreign code is running.
+
+use kernel::deferred_call::DeferredCall;
+use kernel::platform::chip::Chip;
+
+/// Service invoked by an Omniglot runtime when an interrupt preempts foreign
+/// code.
+///
+/// The foreign context has been saved and the application MPU disabled when
+/// this is called. It runs with interrupts disabled, on the kernel stack.
+/// Invocations on the preempted runtime are refused for its duration, while
+/// other runtimes may be used.
+pub trait OmniglotInterruptService {
+    fn service_interrupts(&self);
+}
+
+/// Service all pending interrupts of a chip, followed by all pending deferred
+/// calls, akin to a single iteration of the kernel main loop.
+pub struct ChipInterruptService<'a, C: Chip> {
+    chip: &'a C,
+}
+
+impl<'a, C: Chip> ChipInterruptService<'a, C> {
+    pub const fn new(chip: &'a C) -> Self {
+        ChipInterruptService { chip }
+    }
+}
+
+impl<C: Chip> OmniglotInterruptService for ChipInterruptService<'_, C> {
+    fn service_interrupts(&self) {
+        while self.chip.has_pending_interrupts() {
+            self.chip.service_pending_interrupts();
+        }
+
+        while DeferredCall::has_tasks() {
+            DeferredCall::service_next_pending();
+        }
+    }
+}
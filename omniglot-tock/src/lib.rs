@@ -1,6 +1,33 @@
 #![no_std]
 #![feature(maybe_uninit_as_bytes, maybe_uninit_write_slice, offset_of_enum)]
 
+/// MPU region of an Omniglot runtime.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TockOGMPURegion {
+    Binary,
+    Ram,
+    /// Index into the runtime's additional MPU regions.
+    Additional(usize),
+    /// Buffer temporarily granted to foreign code.
+    Grant,
+}
+
+/// Section of a library, as described by its runtime header.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TockOGRuntimeHeaderSection {
+    /// Initial contents of .data, in the binary.
+    DataSource,
+    Data,
+    Bss,
+    /// Code of the library, excluding its headers.
+    Text,
+    Rodata,
+    Stack,
+    Heap,
+    /// Word holding the heap's program break.
+    HeapBreak,
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum TockOGError {
     BinaryLengthInvalid {
@@ -16,10 +43,75 @@ pub enum TockOGError {
 
     BinaryMagicInvalid,
 
+    BinaryVersionUnsupported {
+        expected: u32,
+        actual: u32,
+    },
+
+    /// The binary was built with an Omniglot header that predates its
+    /// version field, and must be rebuilt.
+    BinaryHeaderUnversioned,
+
+    BinaryHeaderEntryInvalid {
+        tag: u16,
+        desc: &'static str,
+    },
+
+    BinaryHeaderEntryMissing {
+        tag: u16,
+    },
+
     BinarySizeOverflow,
 
+    RamRegionAlignError {
+        expected: usize,
+        actual: usize,
+    },
+
+    RamRegionTooSmall {
+        required_start: *const (),
+        required_length: usize,
+        provided_start: *const (),
+        provided_length: usize,
+    },
+
     MPUConfigError,
 
+    MPURegionAllocError {
+        region: TockOGMPURegion,
+        start: *const u8,
+        size: usize,
+        permissions: kernel::platform::mpu::Permissions,
+    },
+
+    MPURegionRounded {
+        region: TockOGMPURegion,
+        requested: kernel::platform::mpu::Region,
+        granted: kernel::platform::mpu::Region,
+    },
+
+    RuntimeHeaderInvalid {
+        section: TockOGRuntimeHeaderSection,
+        start: *const (),
+        size: usize,
+    },
+
+    InterfaceHashMismatch {
+        expected: u32,
+        actual: Option<u32>,
+    },
+
+    /// The library's `init` function returned a nonzero error code.
+    LibraryInitFailed {
+        error: usize,
+    },
+
+    /// The operation was refused, as an invocation of the library is
+    /// suspended and must be resumed first.
+    RuntimeBusy,
+
+    ProcessBufferError(kernel::process::Error),
+
     OGError(omniglot::OGError),
 }
 
@@ -30,6 +122,8 @@ impl From<omniglot::OGError> for TockOGError {
 }
 
 pub mod binary;
+pub mod fault_policy;
+pub mod interrupt_service;
 pub mod rv32i_c_rt;
 
 // Helper for benchmarks:
@@ -2,8 +2,12 @@ use core::cell::Cell;
 use core::ffi::{c_void, CStr};
 use core::marker::PhantomData;
 use core::mem::MaybeUninit;
+use core::num::NonZeroU32;
 
 use kernel::platform::mpu::{self, MPU};
+use kernel::platform::scheduler_timer::SchedulerTimer;
+use kernel::processbuffer::{ReadableProcessBuffer, WriteableProcessBuffer};
+use kernel::utilities::cells::MapCell;
 
 use omniglot::abi::calling_convention::Stacked;
 use omniglot::abi::calling_convention::{AREG0, AREG1, AREG2, AREG3, AREG4, AREG5, AREG6, AREG7};
@@ -17,12 +21,27 @@ use omniglot::rt::{CallbackContext, CallbackReturn, OGRuntime};
 use omniglot::{OGError, OGResult};
 
 use crate::binary::{OmniglotBinary, OmniglotBinaryParsed};
-use crate::TockOGError;
+use crate::fault_policy::{OmniglotFaultAction, OmniglotFaultPolicy};
+use crate::interrupt_service::OmniglotInterruptService;
+use crate::{TockOGError, TockOGMPURegion, TockOGRuntimeHeaderSection};
+
+use host_call::{TockRv32iCHostCallService, HOST_CALL_ERR_NOSYS};
+
+pub mod console;
+pub mod host_call;
+pub mod split_phase;
+pub mod upcall;
 
 const MCAUSE_INSTRUCTION_ACCESS_FAULT: usize = 1;
 const MCAUSE_ILLEGAL_INSTRUCTION: usize = 2;
 const MCAUSE_ENV_CALL_UMODE: usize = 8;
 
+// Persistent allocations are managed in blocks of this size, tracked in a
+// fixed-size bitmap:
+const PERSISTENT_POOL_BLOCK_SIZE: usize = 16;
+const PERSISTENT_POOL_MAX_BLOCKS: usize = 256;
+const PERSISTENT_POOL_BITMAP_WORDS: usize = PERSISTENT_POOL_MAX_BLOCKS / usize::BITS as usize;
+
 #[repr(C)]
 pub struct CallbackTrampolineFnReturn {
     reg0: usize,
@@ -47,11 +66,67 @@ pub struct TockRv32iCRtCallbackAsmContext {
     runtime: *mut TockRv32iCRtAsmState,
     ret_a0: usize,
     ret_a1: usize,
+    // Address to resume foreign code at. Initialized to the foreign `ra` for
+    // callbacks, and overwritten by the callback handler for host calls:
+    ret_pc: usize,
 }
 
 #[derive(Debug, Clone)]
 pub struct TockRv32iCRtCallbackContext {
     pub arg_regs: [usize; 8],
+
+    // Foreign stack pointer at the time of the callback. Arguments that do
+    // not fit into the argument registers are placed at this address:
+    foreign_stack_ptr: *const (),
+}
+
+impl TockRv32iCRtCallbackContext {
+    pub fn foreign_stack_ptr(&self) -> *const () {
+        self.foreign_stack_ptr
+    }
+
+    /// Read the `idx`-th word of the arguments passed on the foreign stack.
+    ///
+    /// Returns `None` if this word is not contained in foreign memory, as
+    /// determined by the `AllocTracker` of `alloc_scope`.
+    pub fn get_stacked_argument<ID: OGID>(
+        &self,
+        idx: usize,
+        alloc_scope: &AllocScope<'_, TockRv32iCRtAllocChain<'_>, ID>,
+        _access_scope: &AccessScope<ID>,
+    ) -> Option<usize> {
+        let ptr = idx
+            .checked_mul(core::mem::size_of::<usize>())
+            .and_then(|offset| (self.foreign_stack_ptr as usize).checked_add(offset))?
+            as *const usize;
+
+        if !alloc_scope
+            .tracker()
+            .is_valid(ptr as *const (), core::mem::size_of::<usize>())
+            || !ptr.is_aligned()
+        {
+            return None;
+        }
+
+        // Foreign code is not running during a callback, and we hold a
+        // reference to the AccessScope, so this location cannot be modified
+        // concurrently:
+        Some(unsafe { core::ptr::read_volatile(ptr) })
+    }
+
+    /// Read the `idx`-th word of the callback's arguments, following the
+    /// argument registers with the arguments passed on the foreign stack.
+    pub fn get_argument<ID: OGID>(
+        &self,
+        idx: usize,
+        alloc_scope: &AllocScope<'_, TockRv32iCRtAllocChain<'_>, ID>,
+        access_scope: &AccessScope<ID>,
+    ) -> Option<usize> {
+        match idx.checked_sub(self.arg_regs.len()) {
+            None => self.arg_regs.get(idx).copied(),
+            Some(stacked_idx) => self.get_stacked_argument(stacked_idx, alloc_scope, access_scope),
+        }
+    }
 }
 
 impl CallbackContext for TockRv32iCRtCallbackContext {
@@ -65,6 +140,42 @@ pub struct TockRv32iCRtCallbackReturn {
     pub ret_regs: [usize; 2],
 }
 
+impl TockRv32iCRtCallbackReturn {
+    /// Return a value through a caller-provided pointer, as used by the C
+    /// ABI for return values larger than two words.
+    ///
+    /// The foreign caller passes this pointer as a hidden first argument in
+    /// `a0`, which shifts all other arguments by one register. `value` is
+    /// copied to this location, if it is contained in mutable foreign memory,
+    /// as determined by the `AllocTracker` of `alloc_scope`. The pointer is
+    /// further returned in `a0`. Returns `false` if the pointer is invalid.
+    pub fn set_indirect_return<ID: OGID>(
+        &mut self,
+        callback_ctx: &TockRv32iCRtCallbackContext,
+        value: &[u8],
+        alloc_scope: &AllocScope<'_, TockRv32iCRtAllocChain<'_>, ID>,
+        _access_scope: &mut AccessScope<ID>,
+    ) -> bool {
+        let ptr = callback_ctx.arg_regs[0] as *mut u8;
+
+        if !alloc_scope
+            .tracker()
+            .is_valid_mut(ptr as *mut (), value.len())
+        {
+            return false;
+        }
+
+        // We hold a mutable reference to the AccessScope, so there can be no
+        // other references to this foreign memory:
+        unsafe {
+            core::ptr::copy_nonoverlapping(value.as_ptr(), ptr, value.len());
+        }
+
+        self.ret_regs[0] = ptr as usize;
+        true
+    }
+}
+
 impl CallbackReturn for TockRv32iCRtCallbackReturn {
     fn set_return_register(&mut self, reg: usize, value: usize) -> bool {
         if let Some(r) = self.ret_regs.get_mut(reg) {
@@ -87,6 +198,10 @@ const CALLBACK_CONTEXT_PLUS_POINTER_STACKED_SIZE: usize =
 pub struct TockRv32iCRtAllocations {
     ram_region_start: *mut (),
     ram_region_length: usize,
+    // Length of the persistent pool at the end of the RAM region. The pool is
+    // not accessible in general, but only through `PersistentAllocation`
+    // entries of the allocation chain:
+    persistent_pool_length: usize,
     flash_region_start: *mut (),
     flash_region_length: usize,
 }
@@ -106,41 +221,72 @@ impl TockRv32iCRtAllocations {
         (ptr as usize) >= (self.ram_region_start as usize)
             && ((ptr as usize)
                 .checked_add(len)
-                .map(|end| end <= (self.ram_region_start as usize) + self.ram_region_length)
+                .map(|end| {
+                    end <= (self.ram_region_start as usize) + self.ram_region_length
+                        - self.persistent_pool_length
+                })
                 .unwrap_or(false))
     }
+
+    // Whether a pointer is a plausible foreign function address, i.e., it is
+    // suitably aligned and lies within the binary's flash region:
+    fn is_valid_fnptr(&self, ptr: *const ()) -> bool {
+        (ptr as usize) % 2 == 0
+            && (ptr as usize) >= (self.flash_region_start as usize)
+            && (ptr as usize) < (self.flash_region_start as usize) + self.flash_region_length
+    }
 }
 
+type CallbackWrapperFn = unsafe extern "C" fn(
+    *mut c_void,
+    &TockRv32iCRtCallbackContext,
+    &mut TockRv32iCRtCallbackReturn,
+    *mut (),
+    *mut (),
+);
+
 #[derive(Debug)]
 pub struct TockRv32iCCallbackDescriptor<'a> {
     // filled in with an illegal instruction opcode (0x00000000)
     springboard: u32,
-    wrapper: unsafe extern "C" fn(
-        *mut c_void,
-        &TockRv32iCRtCallbackContext,
-        &mut TockRv32iCRtCallbackReturn,
-        *mut (),
-        *mut (),
-    ),
+    wrapper: CallbackWrapperFn,
     context: *mut c_void,
     _lt: PhantomData<&'a mut c_void>,
 }
 
-impl TockRv32iCCallbackDescriptor<'_> {
-    unsafe fn invoke(
-        &self,
-        callback_ctx: &TockRv32iCRtCallbackContext,
-        callback_ret: &mut TockRv32iCRtCallbackReturn,
-        alloc_scope: *mut (),
-        access_scope: *mut (),
-    ) {
-        (self.wrapper)(
-            self.context,
-            callback_ctx,
-            callback_ret,
-            alloc_scope,
-            access_scope,
-        )
+/// Slot for a callback that stays registered beyond the scope of a single
+/// invocation, such that foreign code can retain and call it at any time.
+///
+/// Boards allocate a table of these slots statically and provide it to a
+/// runtime through `set_persistent_callback_slots`. Slots are branded with
+/// the runtime's `ID`, as registered callbacks are invoked with the scopes of
+/// the runtime dispatching them. Thus, a table cannot be shared by runtimes
+/// with different IDs.
+#[repr(C)]
+pub struct TockRv32iCPersistentCallbackSlot<ID: OGID> {
+    // filled in with an illegal instruction opcode (0x00000000)
+    springboard: u32,
+    callback: Cell<Option<(CallbackWrapperFn, *mut c_void)>>,
+    _id: PhantomData<ID>,
+}
+
+impl<ID: OGID> TockRv32iCPersistentCallbackSlot<ID> {
+    pub const fn new() -> Self {
+        TockRv32iCPersistentCallbackSlot {
+            springboard: 0x00000000, // RISC-V unimp
+            callback: Cell::new(None),
+            _id: PhantomData,
+        }
+    }
+
+    fn springboard_ptr(&self) -> *const CallbackTrampolineFn {
+        &self.springboard as *const u32 as *const CallbackTrampolineFn
+    }
+}
+
+impl<ID: OGID> Default for TockRv32iCPersistentCallbackSlot<ID> {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -151,6 +297,13 @@ pub enum TockRv32iCRtAllocChain<'a> {
         TockRv32iCCallbackDescriptor<'a>,
         &'a TockRv32iCRtAllocChain<'a>,
     ),
+    // Allocation in the runtime's persistent pool, with its address and
+    // length, made accessible through `TockRv32iCRt::with_persistent`:
+    PersistentAllocation(*mut (), usize, &'a TockRv32iCRtAllocChain<'a>),
+    // Kernel buffer temporarily mapped into the MPU through
+    // `TockRv32iCRt::with_grant`, with its address, length, and whether it is
+    // writable:
+    Grant(*mut (), usize, bool, &'a TockRv32iCRtAllocChain<'a>),
     Cons(&'a TockRv32iCRtAllocChain<'a>),
 }
 
@@ -165,6 +318,76 @@ impl<'a> TockRv32iCRtAllocChain<'a> {
                 TockRv32iCRtAllocChain::CallbackDescriptor(_, pred) => {
                     cur = pred;
                 }
+                TockRv32iCRtAllocChain::PersistentAllocation(_, _, pred) => {
+                    cur = pred;
+                }
+                TockRv32iCRtAllocChain::Grant(_, _, _, pred) => {
+                    cur = pred;
+                }
+                TockRv32iCRtAllocChain::Cons(pred) => {
+                    cur = pred;
+                }
+            }
+        }
+    }
+
+    // Whether this chain contains any entries that are released at the end
+    // of an `execute` call, i.e., callback descriptors and grants:
+    fn has_scoped_entries(&self) -> bool {
+        let mut cur = self;
+        loop {
+            match cur {
+                TockRv32iCRtAllocChain::BaseAllocations(_) => {
+                    return false;
+                }
+                TockRv32iCRtAllocChain::CallbackDescriptor(_, _)
+                | TockRv32iCRtAllocChain::Grant(_, _, _, _) => {
+                    return true;
+                }
+                TockRv32iCRtAllocChain::PersistentAllocation(_, _, pred) => {
+                    cur = pred;
+                }
+                TockRv32iCRtAllocChain::Cons(pred) => {
+                    cur = pred;
+                }
+            }
+        }
+    }
+
+    // Whether the pointer lies within any grant or persistent allocation on
+    // this chain, and the entry permits the requested access:
+    fn is_valid_entry(&self, ptr: *const (), len: usize, mutable: bool) -> bool {
+        let is_contained = |start: *mut (), size: usize| {
+            (ptr as usize) >= (start as usize)
+                && (ptr as usize)
+                    .checked_add(len)
+                    .map(|end| end <= (start as usize) + size)
+                    .unwrap_or(false)
+        };
+
+        let mut cur = self;
+        loop {
+            match cur {
+                TockRv32iCRtAllocChain::BaseAllocations(_) => {
+                    return false;
+                }
+                TockRv32iCRtAllocChain::Grant(start, size, writable, pred) => {
+                    if is_contained(*start, *size) && (*writable || !mutable) {
+                        return true;
+                    }
+
+                    cur = pred;
+                }
+                TockRv32iCRtAllocChain::CallbackDescriptor(_, pred) => {
+                    cur = pred;
+                }
+                TockRv32iCRtAllocChain::PersistentAllocation(start, size, pred) => {
+                    if is_contained(*start, *size) {
+                        return true;
+                    }
+
+                    cur = pred;
+                }
                 TockRv32iCRtAllocChain::Cons(pred) => {
                     cur = pred;
                 }
@@ -173,20 +396,196 @@ impl<'a> TockRv32iCRtAllocChain<'a> {
     }
 }
 
+/// Memory layout of a library, as returned by `TockRv32iCRt::memory_layout`.
+///
+/// Sections with a size of zero are not present in the library.
+#[derive(Debug, Clone, Copy)]
+pub struct TockRv32iCMemoryLayout {
+    /// Flash region allocated in the MPU, spanning the whole binary.
+    pub flash_region: mpu::Region,
+    /// RAM region allocated in the MPU. It starts at the provided RAM region
+    /// and covers at least the library's memory, rounded up by the MPU, but
+    /// may be smaller than the provided RAM region.
+    pub ram_region: mpu::Region,
+    pub text: mpu::Region,
+    pub rodata: mpu::Region,
+    pub data: mpu::Region,
+    pub bss: mpu::Region,
+    pub heap: mpu::Region,
+    pub stack: mpu::Region,
+    /// Pool reserved through `TockRv32iCRt::set_persistent_pool_size`.
+    pub persistent_pool: Option<mpu::Region>,
+}
+
+impl TockRv32iCMemoryLayout {
+    /// Number of bytes in the RAM region that are not used by any section
+    /// or the persistent pool.
+    pub fn free(&self) -> usize {
+        let used: usize = [self.data, self.bss, self.heap, self.stack]
+            .iter()
+            .chain(self.persistent_pool.iter())
+            .map(|region| region.size())
+            .sum();
+
+        self.ram_region.size().saturating_sub(used)
+    }
+}
+
+impl core::fmt::Display for TockRv32iCMemoryLayout {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut section = |name, region: &mpu::Region| {
+            writeln!(
+                f,
+                " {:<8} {:#010x}-{:#010x} {:>6} B",
+                name,
+                region.start_address() as usize,
+                region.start_address() as usize + region.size(),
+                region.size(),
+            )
+        };
+
+        section("flash", &self.flash_region)?;
+        section(" text", &self.text)?;
+        section(" rodata", &self.rodata)?;
+        section("ram", &self.ram_region)?;
+        section(" stack", &self.stack)?;
+        section(" data", &self.data)?;
+        section(" bss", &self.bss)?;
+        section(" heap", &self.heap)?;
+        if let Some(persistent_pool) = &self.persistent_pool {
+            section(" pool", persistent_pool)?;
+        }
+        write!(f, " free     {:>23} B", self.free())
+    }
+}
+
+/// Handle to an allocation in a runtime's persistent pool, returned by
+/// `TockRv32iCRt::allocate_persistent`.
+///
+/// Unlike stacked allocations, this allocation remains valid across
+/// invocations, until it is passed to `TockRv32iCRt::free_persistent`. Its
+/// memory can be accessed through `TockRv32iCRt::with_persistent`. Handles are
+/// branded with the runtime's `ID`, and are only accepted by the runtime that
+/// allocated them.
+#[derive(Debug)]
+pub struct TockRv32iCPersistentAllocation<ID: OGID> {
+    ptr: *mut (),
+    len: usize,
+    first_block: usize,
+    blocks: usize,
+    _id: PhantomData<ID>,
+}
+
+impl<ID: OGID> TockRv32iCPersistentAllocation<ID> {
+    pub fn ptr(&self) -> *mut () {
+        self.ptr
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+fn persistent_pool_block_allocated(bitmap: &[Cell<usize>], block: usize) -> bool {
+    let bits = usize::BITS as usize;
+    bitmap[block / bits].get() & (1 << (block % bits)) != 0
+}
+
+fn persistent_pool_mark_blocks(
+    bitmap: &[Cell<usize>],
+    first_block: usize,
+    blocks: usize,
+    allocated: bool,
+) {
+    let bits = usize::BITS as usize;
+    for block in first_block..(first_block + blocks) {
+        let word = &bitmap[block / bits];
+        if allocated {
+            word.set(word.get() | (1 << (block % bits)));
+        } else {
+            word.set(word.get() & !(1 << (block % bits)));
+        }
+    }
+}
+
+// First-fit search for a run of `blocks` free blocks among the first
+// `pool_blocks` blocks of the bitmap, returning the index of its first block:
+fn persistent_pool_first_fit(
+    bitmap: &[Cell<usize>],
+    pool_blocks: usize,
+    blocks: usize,
+) -> Option<usize> {
+    let mut run_start = 0;
+    let mut run_length = 0;
+    for block in 0..pool_blocks {
+        if persistent_pool_block_allocated(bitmap, block) {
+            run_start = block + 1;
+            run_length = 0;
+            continue;
+        }
+
+        run_length += 1;
+        if run_length == blocks {
+            return Some(run_start);
+        }
+    }
+
+    None
+}
+
 unsafe impl AllocTracker for TockRv32iCRtAllocChain<'_> {
     fn is_valid(&self, ptr: *const (), len: usize) -> bool {
-        self.get_base_allocations().is_valid(ptr, len)
+        self.get_base_allocations().is_valid(ptr, len) || self.is_valid_entry(ptr, len, false)
     }
 
     fn is_valid_mut(&self, ptr: *mut (), len: usize) -> bool {
         self.get_base_allocations().is_valid_mut(ptr, len)
+            || self.is_valid_entry(ptr as *const (), len, true)
     }
 }
 
+/// Machine state captured when foreign code traps for any reason other than a
+/// function return or callback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TockRv32iCFault {
+    pub mcause: usize,
+    pub mtval: usize,
+    pub mepc: usize,
+    pub foreign_stack_ptr: *const (),
+}
+
+impl TockRv32iCFault {
+    /// Whether the foreign function was aborted because it exceeded its
+    /// execution-time budget. In this case, `mcause` holds the cause of the
+    /// interrupt that preempted it.
+    pub fn is_timeout(&self) -> bool {
+        (self.mcause as isize) < 0
+    }
+}
+
+/// Status of a foreign function invocation, as encoded into the
+/// `TockRv32iCInvokeResInner` by the protection-domain switch.
+#[derive(Debug, Clone, Copy)]
 #[repr(usize)]
-enum TockRv32iCInvokeErr {
+pub enum TockRv32iCInvokeErr {
     NoError,
     NotCalled,
+    // The foreign function faulted during this invocation:
+    Fault(TockRv32iCFault),
+    // The foreign function exceeded its execution-time budget and was
+    // aborted. This is handled like a fault:
+    Timeout(TockRv32iCFault),
+    // The invocation was refused, as the runtime faulted previously:
+    RuntimeFaulted,
+    // The foreign function was preempted and suspended. It must be resumed
+    // through `TockRv32iCRt::resume`:
+    Suspended,
+    // The invocation was refused, as another invocation is suspended:
+    RuntimeBusy,
 }
 
 // Depending on the size of the return value, it will be either passed as a
@@ -219,6 +618,14 @@ impl<RT: Rv32iCBaseRt, T> TockRv32iCInvokeRes<RT, T> {
             ),
 
             TockRv32iCInvokeErr::NoError => Ok(()),
+
+            // Details on the fault are retained in the runtime and can be
+            // queried through `TockRv32iCRt::fault`:
+            TockRv32iCInvokeErr::Fault(_)
+            | TockRv32iCInvokeErr::Timeout(_)
+            | TockRv32iCInvokeErr::RuntimeFaulted
+            | TockRv32iCInvokeErr::Suspended
+            | TockRv32iCInvokeErr::RuntimeBusy => Err(OGError::InternalError),
         }
     }
 }
@@ -315,6 +722,29 @@ pub struct TockRv32iCRtAsmState {
     // Foreign stack bottom (inclusive). Last usable stack address:
     foreign_stack_bottom: *mut (),
 
+    // Set when foreign code faulted. The protection-domain switch assembly
+    // refuses to run any foreign code while this flag is set:
+    faulted: Cell<bool>,
+
+    // Set when a foreign invocation has been suspended. Its register file is
+    // saved in `suspended_regs`, indexed by register number, with the
+    // program counter in place of `x0`. While this flag is set, the
+    // protection-domain switch assembly refuses all invocations except for a
+    // resume request:
+    suspended: Cell<bool>,
+    suspended_regs: [Cell<usize>; 32],
+
+    // Whether the current outermost invocation may be suspended. Cleared by
+    // the protection-domain switch assembly for invocations that pass
+    // arguments on the stack:
+    suspendable: Cell<bool>,
+
+    // Set while servicing kernel interrupts from within a preempted foreign
+    // invocation. The protection-domain switch assembly refuses all
+    // invocations while this flag is set, as they would reuse the preempted
+    // function's stack:
+    servicing_interrupts: Cell<bool>,
+
     // TODO: doc
     ram_region_start: *mut (),
     ram_region_length: usize,
@@ -347,14 +777,82 @@ pub struct TockRv32iCRt<ID: OGID, M: MPU + 'static> {
     init_addr: *const (),
     fntab_addr: *const (),
     fntab_length: usize,
+    fntab_names_addr: *const (),
+
+    // Heap region of the library (start and size), and the location of its
+    // current program break, if it has a heap:
+    heap: Option<(*const (), usize, *const usize)>,
+
+    // Regions allocated in the MPU, and the library's sections within them:
+    memory_layout: TockRv32iCMemoryLayout,
+
+    // End of the statically allocated part of the RAM region (stack, .data,
+    // .bss, and .heap sections):
+    ram_static_end: usize,
+
+    // Pool of foreign memory at the end of the RAM region for allocations
+    // that outlive a single invocation, as its start address and number of
+    // blocks. Each bit in the bitmap marks an allocated block:
+    persistent_pool: Cell<Option<(*mut (), usize)>>,
+    persistent_pool_bitmap: [Cell<usize>; PERSISTENT_POOL_BITMAP_WORDS],
+
+    // Information on the fault that caused `asm_state.faulted` to be set:
+    fault: Cell<Option<TockRv32iCFault>>,
+
+    // Number of times this runtime has been reset through `reset`:
+    reset_count: Cell<usize>,
+
+    // Policy consulted whenever foreign code faults, and the number of
+    // faults and policy-initiated restarts so far. A restart requested by
+    // the policy is deferred until the next invocation:
+    fault_policy: &'static dyn OmniglotFaultPolicy,
+    fault_count: Cell<usize>,
+    restart_count: Cell<usize>,
+    restart_pending: Cell<bool>,
+    // Error of the last policy-initiated restart, if it failed:
+    restart_error: Cell<Option<TockOGError>>,
+
+    // Optional execution-time budget for foreign invocations, enforced
+    // through a scheduler timer that is armed for the duration of each
+    // outermost `execute`:
+    invoke_budget: Cell<Option<(&'static dyn SchedulerTimer, NonZeroU32)>>,
+    invoke_budget_armed: Cell<bool>,
+
+    // Whether to suspend, rather than abort, foreign invocations that exceed
+    // their execution-time budget:
+    split_phase: Cell<bool>,
+
+    // Number of callbacks of this runtime currently executing. Foreign
+    // functions invoked from within a callback are nested in the invocation
+    // that issued the callback:
+    callback_depth: Cell<usize>,
+
+    // Table of callbacks registered for the lifetime of this runtime:
+    persistent_callbacks: Cell<&'static [TockRv32iCPersistentCallbackSlot<ID>]>,
+
+    // Dispatch table of host calls provided to this runtime, by number:
+    host_calls: Cell<&'static [(usize, &'static dyn TockRv32iCHostCallService)]>,
+
+    // Optional service to handle kernel interrupts while foreign code is
+    // running. Without it, interrupts are only handled once the outermost
+    // invocation returns:
+    interrupt_service: Cell<Option<&'static dyn OmniglotInterruptService>>,
+
+    // Cycle counter value at the first interrupt deferred during the current
+    // invocation, and the maximum latency (in cycles) that foreign code
+    // added to any interrupt:
+    interrupt_deferred_since: Cell<Option<usize>>,
+    max_interrupt_latency_cycles: Cell<usize>,
 
     mpu: &'static M,
-    mpu_config: M::MpuConfig,
+    // Held in a `MapCell`, such that grants can temporarily add regions:
+    mpu_config: MapCell<M::MpuConfig>,
 
     _id: PhantomData<ID>,
 }
 
 impl<ID: OGID, M: MPU + 'static> TockRv32iCRt<ID, M> {
+    #[allow(clippy::too_many_arguments)]
     pub unsafe fn new(
         mpu: &'static M,
         binary: OmniglotBinary,
@@ -366,6 +864,8 @@ impl<ID: OGID, M: MPU + 'static> TockRv32iCRt<ID, M> {
                 kernel::platform::mpu::Permissions,
             ),
         >,
+        fault_policy: &'static dyn OmniglotFaultPolicy,
+        interface_hash: Option<u32>,
         ogid: ID,
     ) -> Result<
         (
@@ -383,127 +883,1151 @@ impl<ID: OGID, M: MPU + 'static> TockRv32iCRt<ID, M> {
         // constants are instantiated outside of the `impl` block.
         let _: () = assert!(core::mem::offset_of!(Self, asm_state) == 0);
 
-        // Parse the binary and extract the necessary offsets:
-        let OmniglotBinaryParsed {
-            rthdr_addr,
-            init_addr,
-            fntab_addr,
-            fntab_length,
-        } = binary.parse()?;
+        // Parse the binary and extract the necessary offsets:
+        let OmniglotBinaryParsed {
+            rthdr_addr,
+            rthdr,
+            init_addr,
+            fntab_addr,
+            fntab_length,
+            fntab_names_addr,
+            interface_hash: binary_interface_hash,
+            memory_requirements,
+            ..
+        } = binary.parse()?;
+
+        // If provided with the hash of the interface that our bindings were
+        // generated from, ensure that the library was built against the same
+        // interface (see `interface_hash!`):
+        if let Some(expected) = interface_hash {
+            if binary_interface_hash != Some(expected) {
+                return Err(TockOGError::InterfaceHashMismatch {
+                    expected,
+                    actual: binary_interface_hash,
+                });
+            }
+        }
+
+        // If the library declares its memory requirements, check them against
+        // the provided RAM region before making any further use of it:
+        if let Some(requirements) = memory_requirements {
+            if ram_region_start as usize % requirements.ram_alignment != 0 {
+                return Err(TockOGError::RamRegionAlignError {
+                    expected: requirements.ram_alignment,
+                    actual: ram_region_start as usize % requirements.ram_alignment,
+                });
+            }
+
+            let required_end = (requirements.ram_start as usize)
+                .checked_add(requirements.ram_length)
+                .ok_or(TockOGError::BinarySizeOverflow)?;
+            if (requirements.ram_start as usize) < ram_region_start as usize
+                || required_end > ram_region_start as usize + ram_region_length
+            {
+                return Err(TockOGError::RamRegionTooSmall {
+                    required_start: requirements.ram_start,
+                    required_length: requirements.ram_length,
+                    provided_start: ram_region_start as *const (),
+                    provided_length: ram_region_length,
+                });
+            }
+        }
+
+        let section = |section| {
+            let (start, size) = rthdr.section(section);
+            mpu::Region::new(start as *const u8, size)
+        };
+
+        // End of the statically allocated part of the RAM region (stack,
+        // .data, .bss, and .heap sections). The MPU must allocate a RAM region
+        // covering at least these sections and any memory that the library
+        // declared to require:
+        let ram_static_end = [
+            TockOGRuntimeHeaderSection::Stack,
+            TockOGRuntimeHeaderSection::Data,
+            TockOGRuntimeHeaderSection::Bss,
+            TockOGRuntimeHeaderSection::Heap,
+        ]
+        .into_iter()
+        .map(|s| {
+            let region = section(s);
+            (region.start_address() as usize).saturating_add(region.size())
+        })
+        .max()
+        .unwrap_or(0);
+        let ram_required_end = memory_requirements.map_or(ram_static_end, |requirements| {
+            core::cmp::max(
+                ram_static_end,
+                requirements.ram_start as usize + requirements.ram_length,
+            )
+        });
+        let ram_min_size = ram_required_end.saturating_sub(ram_region_start as usize);
+
+        // Create an MPU configuration that sets up appropriate permissions for
+        // the Omniglot binary. Each region is allocated within the provided
+        // memory, with at least the minimum size. The MPU may round regions
+        // to its granularity, so record the regions that it actually
+        // allocated:
+        let mut mpu_config = mpu
+            .new_config()
+            .ok_or_else(|| TockOGError::MPUConfigError)?;
+
+        let mut allocate_region = |region, start, max_size, min_size, permissions| {
+            mpu.allocate_region(start, max_size, min_size, permissions, &mut mpu_config)
+                .ok_or(TockOGError::MPURegionAllocError {
+                    region,
+                    start,
+                    size: min_size,
+                    permissions,
+                })
+        };
+
+        // All of the binary must be accessible, as we do not know where its
+        // code is located, but nothing beyond it:
+        let flash_region = allocate_region(
+            TockOGMPURegion::Binary,
+            binary.binary_start as *const u8,
+            binary.binary_length,
+            binary.binary_length,
+            mpu::Permissions::ReadExecuteOnly,
+        )?;
+
+        let ram_region = allocate_region(
+            TockOGMPURegion::Ram,
+            ram_region_start as *mut u8 as *const _,
+            ram_region_length,
+            ram_min_size,
+            mpu::Permissions::ReadWriteOnly,
+        )?;
+
+        for (idx, (region, permissions)) in addl_mpu_regions.enumerate() {
+            allocate_region(
+                TockOGMPURegion::Additional(idx),
+                region.start_address(),
+                region.size(),
+                region.size(),
+                permissions,
+            )?;
+        }
+
+        // Validate the allocated regions. Foreign code must not be able to
+        // access any memory beyond the binary and RAM region:
+        let requested_flash_region =
+            mpu::Region::new(binary.binary_start as *const u8, binary.binary_length);
+        if flash_region != requested_flash_region {
+            return Err(TockOGError::MPURegionRounded {
+                region: TockOGMPURegion::Binary,
+                requested: requested_flash_region,
+                granted: flash_region,
+            });
+        }
+
+        // The allocated RAM region may be smaller than the provided one, but
+        // must start at its beginning and cover the library's memory. In this
+        // case, we only use the allocated region:
+        let requested_ram_region =
+            mpu::Region::new(ram_region_start as *mut u8 as *const _, ram_region_length);
+        if !core::ptr::eq(ram_region.start_address(), ram_region_start as *const u8)
+            || ram_region.size() < ram_min_size
+            || ram_region.size() > ram_region_length
+        {
+            return Err(TockOGError::MPURegionRounded {
+                region: TockOGMPURegion::Ram,
+                requested: requested_ram_region,
+                granted: ram_region,
+            });
+        }
+        let ram_region_start = ram_region.start_address() as *mut ();
+        let ram_region_length = ram_region.size();
+
+        // The stack and the .data and .bss sections must be contained in the
+        // RAM region, as the init function writes to them:
+        for section in [
+            TockOGRuntimeHeaderSection::Stack,
+            TockOGRuntimeHeaderSection::Data,
+            TockOGRuntimeHeaderSection::Bss,
+        ] {
+            rthdr.check_section(section, ram_region_start as *const (), ram_region_length)?;
+        }
+
+        // Extract the optional heap region. It must be contained in the RAM
+        // region, along with the word holding the program break:
+        let heap = if rthdr.heap_size == 0 {
+            None
+        } else {
+            for section in [
+                TockOGRuntimeHeaderSection::Heap,
+                TockOGRuntimeHeaderSection::HeapBreak,
+            ] {
+                rthdr.check_section(section, ram_region_start as *const (), ram_region_length)?;
+            }
+
+            if rthdr.heap_brk_ptr as usize % core::mem::align_of::<usize>() != 0 {
+                return Err(TockOGError::RuntimeHeaderInvalid {
+                    section: TockOGRuntimeHeaderSection::HeapBreak,
+                    start: rthdr.heap_brk_ptr as *const (),
+                    size: core::mem::size_of::<usize>(),
+                });
+            }
+
+            Some((rthdr.heap_start, rthdr.heap_size, rthdr.heap_brk_ptr))
+        };
+
+        let memory_layout = TockRv32iCMemoryLayout {
+            flash_region,
+            ram_region,
+            text: section(TockOGRuntimeHeaderSection::Text),
+            rodata: section(TockOGRuntimeHeaderSection::Rodata),
+            data: section(TockOGRuntimeHeaderSection::Data),
+            bss: section(TockOGRuntimeHeaderSection::Bss),
+            heap: section(TockOGRuntimeHeaderSection::Heap),
+            stack: section(TockOGRuntimeHeaderSection::Stack),
+            persistent_pool: None,
+        };
+
+        // Construct an initial runtime instance. We don't yet know where our
+        // `foreign_stack_top` should be placed -- that will depend on how much
+        // static data `init` will place at the top of memory. We need to set
+        // the stack pointer equal to some valid value though, and thus we --
+        // for now -- set it to be nthe top of memory.
+        let ram_region_end = unsafe { ram_region_start.byte_add(ram_region_length) };
+
+        let rt = TockRv32iCRt {
+            asm_state: TockRv32iCRtAsmState {
+                foreign_stack_ptr: Cell::new(ram_region_end),
+                foreign_stack_bottom: ram_region_start,
+                faulted: Cell::new(false),
+                suspended: Cell::new(false),
+                suspended_regs: [const { Cell::new(0) }; 32],
+                suspendable: Cell::new(false),
+                servicing_interrupts: Cell::new(false),
+                ram_region_start,
+                ram_region_length,
+                active_alloc_scope: Cell::new(core::ptr::null_mut()),
+                mpu: mpu as *const _ as *const _,
+            },
+
+            binary,
+            rthdr_addr,
+            init_addr,
+            fntab_addr,
+            fntab_length,
+            fntab_names_addr,
+
+            heap,
+            ram_static_end,
+            memory_layout,
+
+            persistent_pool: Cell::new(None),
+            persistent_pool_bitmap: [const { Cell::new(0) }; PERSISTENT_POOL_BITMAP_WORDS],
+
+            fault: Cell::new(None),
+            reset_count: Cell::new(0),
+
+            fault_policy,
+            fault_count: Cell::new(0),
+            restart_count: Cell::new(0),
+            restart_pending: Cell::new(false),
+            restart_error: Cell::new(None),
+
+            invoke_budget: Cell::new(None),
+            invoke_budget_armed: Cell::new(false),
+
+            split_phase: Cell::new(false),
+
+            callback_depth: Cell::new(0),
+
+            persistent_callbacks: Cell::new(&[]),
+
+            host_calls: Cell::new(&[]),
+
+            interrupt_service: Cell::new(None),
+            interrupt_deferred_since: Cell::new(None),
+            max_interrupt_latency_cycles: Cell::new(0),
+
+            mpu,
+            mpu_config: MapCell::new(mpu_config),
+
+            _id: PhantomData::<ID>,
+        };
+
+        let mut alloc_scope = unsafe {
+            AllocScope::new(
+                TockRv32iCRtAllocChain::BaseAllocations(rt.base_allocations()),
+                ogid.get_imprint(),
+            )
+        };
+
+        rt.init_with_alloc_scope(&mut alloc_scope)?;
+
+        Ok((rt, alloc_scope, unsafe {
+            AccessScope::new(ogid.get_imprint())
+        }))
+    }
+
+    /// Reset and re-initialize this runtime, for instance after a fault.
+    ///
+    /// This restores the foreign stack pointer and re-runs the library's
+    /// `init` function, which re-initializes its `.data` and `.bss`
+    /// sections. The runtime's root allocation and access scopes are replaced
+    /// with fresh instances: requiring exclusive references to them ensures
+    /// that no allocations or references into foreign memory created before
+    /// the reset remain live.
+    ///
+    /// If the library faults or its `init` function returns an error during
+    /// re-initialization, this returns an error and the runtime remains in a
+    /// faulted state.
+    pub fn reset(
+        &self,
+        alloc_scope: &mut AllocScope<'static, TockRv32iCRtAllocChain<'static>, ID>,
+        access_scope: &mut AccessScope<ID>,
+    ) -> Result<(), TockOGError> {
+        let id_imprint = alloc_scope.id_imprint();
+
+        // Invalidate all outstanding handles derived from the old scopes:
+        *alloc_scope = unsafe {
+            AllocScope::new(
+                TockRv32iCRtAllocChain::BaseAllocations(self.base_allocations()),
+                id_imprint,
+            )
+        };
+        *access_scope = unsafe { AccessScope::new(id_imprint) };
+
+        // Restore the foreign stack pointer to the top of memory, as in
+        // `new`. `init` will provide us with the actual stack top:
+        self.asm_state.foreign_stack_ptr.set(unsafe {
+            self.asm_state
+                .ram_region_start
+                .byte_add(self.asm_state.ram_region_length)
+        });
+
+        // Allow foreign code to run again:
+        self.asm_state.faulted.set(false);
+        self.fault.set(None);
+        self.restart_pending.set(false);
+        self.restart_error.set(None);
+        self.asm_state.suspended.set(false);
+
+        self.reset_count.set(self.reset_count.get() + 1);
+
+        self.init_with_alloc_scope(alloc_scope)?;
+
+        Ok(())
+    }
+
+    /// Number of times this runtime has been reset through `reset`.
+    pub fn reset_count(&self) -> usize {
+        self.reset_count.get()
+    }
+
+    /// Number of times foreign code has faulted in this runtime.
+    pub fn fault_count(&self) -> usize {
+        self.fault_count.get()
+    }
+
+    /// Number of times this runtime has been restarted by its fault policy.
+    pub fn restart_count(&self) -> usize {
+        self.restart_count.get()
+    }
+
+    /// Error of the last restart performed by the fault policy, if the
+    /// library faulted or its `init` function returned an error. The runtime
+    /// then remains in a faulted state, refusing all invocations until it is
+    /// restarted again or re-initialized through `reset`.
+    pub fn restart_error(&self) -> Option<TockOGError> {
+        let restart_error = self.restart_error.take();
+        self.restart_error.set(restart_error.clone());
+        restart_error
+    }
+
+    /// Limit the execution time of every invocation of foreign code to
+    /// `budget_us` microseconds.
+    ///
+    /// The budget applies to each outermost call to `execute`, including all
+    /// nested callbacks and host calls, and to every re-initialization of the
+    /// library through `reset` or its fault policy. The initial `init` run by
+    /// `new` is not budgeted. The budget is enforced using the provided
+    /// scheduler timer, which must not be in use by the kernel while it
+    /// executes foreign code. The timer must raise an interrupt on expiry.
+    ///
+    /// Kernel code, such as callbacks, is never preempted. Instead, the timer
+    /// is re-armed whenever foreign code resumes, such that foreign code is
+    /// preempted as soon as it continues executing after the budget has been
+    /// exceeded. Foreign code exceeding its budget is aborted, returning an
+    /// error. The runtime then handles this like a fault, consulting its
+    /// fault policy.
+    pub fn set_invoke_budget(&self, timer: &'static dyn SchedulerTimer, budget_us: NonZeroU32) {
+        self.invoke_budget.set(Some((timer, budget_us)));
+    }
+
+    /// Remove any execution-time budget set through `set_invoke_budget`.
+    pub fn clear_invoke_budget(&self) {
+        self.invoke_budget.set(None);
+    }
+
+    /// Suspend, rather than abort, foreign invocations that exceed their
+    /// execution-time budget, as set through `set_invoke_budget`.
+    ///
+    /// A suspended invocation returns an error to its caller. Its state is
+    /// retained in the runtime and it can be continued through `resume`,
+    /// for instance from a deferred call (see `split_phase`). While an
+    /// invocation is suspended, the runtime refuses all other invocations
+    /// and stacked allocations.
+    ///
+    /// Only invocations that pass all their arguments in registers, and that
+    /// do not have any callbacks or grants set up, can be suspended, as those
+    /// are released when the original invocation returns. Such invocations
+    /// are aborted instead. Similarly, `init` is never suspended.
+    pub fn set_split_phase(&self, enabled: bool) {
+        self.split_phase.set(enabled);
+    }
+
+    /// Reserve a pool of `size` bytes at the end of the RAM region for
+    /// persistent allocations.
+    ///
+    /// The pool must not overlap with the library's statically allocated
+    /// memory, and is limited to `PERSISTENT_POOL_MAX_BLOCKS` blocks of
+    /// `PERSISTENT_POOL_BLOCK_SIZE` bytes. It can only be resized while there
+    /// are no persistent allocations. Persistent allocations are not affected
+    /// by re-initializing the library.
+    ///
+    /// Memory in the pool is only valid for the allocations placed on the
+    /// allocation chain through `with_persistent`. Thus, as for `reset`, the
+    /// runtime's root allocation scope is replaced with a fresh instance,
+    /// such that no handles derived from the old scope remain live.
+    pub fn set_persistent_pool_size(
+        &self,
+        size: usize,
+        alloc_scope: &mut AllocScope<'static, TockRv32iCRtAllocChain<'static>, ID>,
+    ) -> OGResult<()> {
+        if self
+            .persistent_pool_bitmap
+            .iter()
+            .any(|word| word.get() != 0)
+        {
+            return Err(OGError::InternalError);
+        }
+
+        let blocks = size.div_ceil(PERSISTENT_POOL_BLOCK_SIZE);
+        if blocks > PERSISTENT_POOL_MAX_BLOCKS {
+            return Err(OGError::AllocNoMem);
+        }
+
+        let ram_region_end =
+            self.asm_state.ram_region_start as usize + self.asm_state.ram_region_length;
+        let pool_start = ram_region_end
+            .checked_sub(blocks * PERSISTENT_POOL_BLOCK_SIZE)
+            .map(|start| start & !(PERSISTENT_POOL_BLOCK_SIZE - 1))
+            .ok_or(OGError::AllocNoMem)?;
+
+        if pool_start < self.ram_static_end {
+            return Err(OGError::AllocNoMem);
+        }
+
+        self.persistent_pool.set(if blocks == 0 {
+            None
+        } else {
+            Some((pool_start as *mut (), blocks))
+        });
+
+        let id_imprint = alloc_scope.id_imprint();
+        *alloc_scope = unsafe {
+            AllocScope::new(
+                TockRv32iCRtAllocChain::BaseAllocations(self.base_allocations()),
+                id_imprint,
+            )
+        };
+
+        Ok(())
+    }
+
+    // Whether `allocation` was made in this runtime's pool, and has not been
+    // freed yet:
+    fn owns_persistent(&self, allocation: &TockRv32iCPersistentAllocation<ID>) -> bool {
+        let Some((pool_start, pool_blocks)) = self.persistent_pool.get() else {
+            return false;
+        };
+
+        let expected_ptr =
+            pool_start as usize + allocation.first_block * PERSISTENT_POOL_BLOCK_SIZE;
+
+        allocation.ptr as usize == expected_ptr
+            && allocation.blocks != 0
+            && allocation.first_block + allocation.blocks <= pool_blocks
+            && (allocation.first_block..(allocation.first_block + allocation.blocks))
+                .all(|block| persistent_pool_block_allocated(&self.persistent_pool_bitmap, block))
+    }
+
+    /// Allocate foreign memory in the persistent pool, which remains valid
+    /// until it is freed through `free_persistent`.
+    ///
+    /// Allocations are aligned to `PERSISTENT_POOL_BLOCK_SIZE`. Returns
+    /// `AllocNoMem` if there is no pool, no sufficiently large contiguous
+    /// free region in it, or the layout requires a larger alignment.
+    pub fn allocate_persistent(
+        &self,
+        layout: core::alloc::Layout,
+    ) -> OGResult<TockRv32iCPersistentAllocation<ID>> {
+        let (pool_start, pool_blocks) = self.persistent_pool.get().ok_or(OGError::AllocNoMem)?;
+
+        if layout.align() > PERSISTENT_POOL_BLOCK_SIZE {
+            return Err(OGError::AllocNoMem);
+        }
+
+        let blocks = core::cmp::max(1, layout.size().div_ceil(PERSISTENT_POOL_BLOCK_SIZE));
+
+        let run_start =
+            persistent_pool_first_fit(&self.persistent_pool_bitmap, pool_blocks, blocks)
+                .ok_or(OGError::AllocNoMem)?;
+        persistent_pool_mark_blocks(&self.persistent_pool_bitmap, run_start, blocks, true);
+
+        Ok(TockRv32iCPersistentAllocation {
+            ptr: unsafe { pool_start.byte_add(run_start * PERSISTENT_POOL_BLOCK_SIZE) },
+            len: layout.size(),
+            first_block: run_start,
+            blocks,
+            _id: PhantomData,
+        })
+    }
+
+    /// Free an allocation made through `allocate_persistent` on this runtime.
+    ///
+    /// Returns `InternalError` if the allocation belongs to another runtime
+    /// with the same `ID`.
+    pub fn free_persistent(&self, allocation: TockRv32iCPersistentAllocation<ID>) -> OGResult<()> {
+        if !self.owns_persistent(&allocation) {
+            return Err(OGError::InternalError);
+        }
+
+        persistent_pool_mark_blocks(
+            &self.persistent_pool_bitmap,
+            allocation.first_block,
+            allocation.blocks,
+            false,
+        );
+
+        Ok(())
+    }
+
+    /// Make a persistent allocation accessible in an allocation scope.
+    ///
+    /// This places the allocation on the allocation chain for the duration of
+    /// `fun`, which is passed its pointer and the inner allocation scope.
+    /// Returns `InternalError` if the allocation belongs to another runtime
+    /// with the same `ID`.
+    pub fn with_persistent<F, R>(
+        &self,
+        allocation: &TockRv32iCPersistentAllocation<ID>,
+        alloc_scope: &mut AllocScope<'_, TockRv32iCRtAllocChain<'_>, ID>,
+        fun: F,
+    ) -> OGResult<R>
+    where
+        F: for<'b> FnOnce(*mut (), &'b mut AllocScope<'_, TockRv32iCRtAllocChain<'_>, ID>) -> R,
+    {
+        if !self.owns_persistent(allocation) {
+            return Err(OGError::InternalError);
+        }
+
+        let mut inner_alloc_scope: AllocScope<'_, TockRv32iCRtAllocChain<'_>, ID> = unsafe {
+            AllocScope::new(
+                TockRv32iCRtAllocChain::PersistentAllocation(
+                    allocation.ptr,
+                    allocation.len,
+                    alloc_scope.tracker(),
+                ),
+                alloc_scope.id_imprint(),
+            )
+        };
+
+        // The inner alloc scope will be popped from the stack once we leave
+        // this closure:
+        Ok(fun(allocation.ptr, &mut inner_alloc_scope))
+    }
+
+    /// Temporarily grant foreign code access to a kernel buffer, without
+    /// copying it into the library's RAM region.
+    ///
+    /// The buffer is mapped into the MPU with read-only or read-write
+    /// permissions for the duration of `fun`, and placed on the allocation
+    /// chain, such that foreign pointers into it are valid. It must not be
+    /// empty, and the MPU must be able to protect it exactly, without
+    /// exposing any adjacent memory. Once `fun` returns, the region is
+    /// revoked again.
+    ///
+    /// Foreign code must not retain pointers into the buffer beyond `fun`.
+    /// Thus, grants are refused with `TockOGError::RuntimeBusy` while an
+    /// invocation is suspended, and a split-phase invocation started within
+    /// `fun` will fault when it accesses the buffer after being resumed.
+    pub fn with_grant<F, R>(
+        &self,
+        buffer: &mut [u8],
+        writable: bool,
+        alloc_scope: &mut AllocScope<'_, TockRv32iCRtAllocChain<'_>, ID>,
+        fun: F,
+    ) -> Result<R, TockOGError>
+    where
+        F: for<'b> FnOnce(*mut u8, &'b mut AllocScope<'_, TockRv32iCRtAllocChain<'_>, ID>) -> R,
+    {
+        unsafe {
+            self.with_grant_raw(
+                buffer.as_mut_ptr(),
+                buffer.len(),
+                writable,
+                alloc_scope,
+                fun,
+            )
+        }
+    }
+
+    /// Temporarily grant foreign code read-only access to a buffer allowed
+    /// by a userspace process, as with `with_grant`.
+    ///
+    /// The buffer is entered for the duration of `fun`, which fails if the
+    /// process is no longer alive. The process' own MPU configuration is not
+    /// affected: Tock re-applies it before switching to the process.
+    pub fn with_readable_process_buffer<B, F, R>(
+        &self,
+        buffer: &B,
+        alloc_scope: &mut AllocScope<'_, TockRv32iCRtAllocChain<'_>, ID>,
+        fun: F,
+    ) -> Result<R, TockOGError>
+    where
+        B: ReadableProcessBuffer,
+        F: for<'b> FnOnce(*mut u8, &'b mut AllocScope<'_, TockRv32iCRtAllocChain<'_>, ID>) -> R,
+    {
+        buffer
+            .enter(|slice| unsafe {
+                // The process slice remains valid while entered:
+                self.with_grant_raw(
+                    slice as *const _ as *const u8 as *mut u8,
+                    slice.len(),
+                    false,
+                    alloc_scope,
+                    fun,
+                )
+            })
+            .map_err(TockOGError::ProcessBufferError)?
+    }
+
+    /// Temporarily grant foreign code read-write access to a buffer allowed
+    /// by a userspace process, as with `with_readable_process_buffer`.
+    pub fn with_writeable_process_buffer<B, F, R>(
+        &self,
+        buffer: &B,
+        alloc_scope: &mut AllocScope<'_, TockRv32iCRtAllocChain<'_>, ID>,
+        fun: F,
+    ) -> Result<R, TockOGError>
+    where
+        B: WriteableProcessBuffer,
+        F: for<'b> FnOnce(*mut u8, &'b mut AllocScope<'_, TockRv32iCRtAllocChain<'_>, ID>) -> R,
+    {
+        buffer
+            .mut_enter(|slice| unsafe {
+                // The process slice remains valid while entered, and consists
+                // of `Cell`s, so it may be modified through shared references:
+                self.with_grant_raw(
+                    slice as *const _ as *const u8 as *mut u8,
+                    slice.len(),
+                    true,
+                    alloc_scope,
+                    fun,
+                )
+            })
+            .map_err(TockOGError::ProcessBufferError)?
+    }
+
+    // Caller must ensure that `len` bytes at `ptr` are valid (and, if
+    // `writable`, may be modified) for the duration of `fun`:
+    unsafe fn with_grant_raw<F, R>(
+        &self,
+        ptr: *mut u8,
+        len: usize,
+        writable: bool,
+        alloc_scope: &mut AllocScope<'_, TockRv32iCRtAllocChain<'_>, ID>,
+        fun: F,
+    ) -> Result<R, TockOGError>
+    where
+        F: for<'b> FnOnce(*mut u8, &'b mut AllocScope<'_, TockRv32iCRtAllocChain<'_>, ID>) -> R,
+    {
+        if self.is_suspended() {
+            return Err(TockOGError::RuntimeBusy);
+        }
+
+        let permissions = if writable {
+            mpu::Permissions::ReadWriteOnly
+        } else {
+            mpu::Permissions::ReadOnly
+        };
+
+        let alloc_error = TockOGError::MPURegionAllocError {
+            region: TockOGMPURegion::Grant,
+            start: ptr as *const u8,
+            size: len,
+            permissions,
+        };
+
+        if len == 0 {
+            return Err(alloc_error);
+        }
+
+        let region = self
+            .mpu_config
+            .map(|mpu_config| {
+                self.mpu
+                    .allocate_region(ptr as *const u8, len, len, permissions, mpu_config)
+            })
+            .flatten()
+            .ok_or_else(|| alloc_error.clone())?;
+
+        // The MPU may round regions to its granularity. Granting access to
+        // any memory outside of the buffer would be unsound:
+        if !core::ptr::eq(region.start_address(), ptr) || region.size() != len {
+            self.revoke_grant(region)?;
+            return Err(alloc_error);
+        }
+
+        let mut inner_alloc_scope: AllocScope<'_, TockRv32iCRtAllocChain<'_>, ID> = unsafe {
+            AllocScope::new(
+                TockRv32iCRtAllocChain::Grant(ptr as *mut (), len, writable, alloc_scope.tracker()),
+                alloc_scope.id_imprint(),
+            )
+        };
+
+        let res = fun(ptr, &mut inner_alloc_scope);
+
+        self.revoke_grant(region)?;
+
+        Ok(res)
+    }
+
+    fn revoke_grant(&self, region: mpu::Region) -> Result<(), TockOGError> {
+        let removed = self
+            .mpu_config
+            .map(|mpu_config| self.mpu.remove_memory_region(region, mpu_config))
+            .is_some_and(|res| res.is_ok());
+
+        if !removed {
+            // We cannot guarantee that the library lost access to the
+            // granted memory. Stop it, such that it cannot run again until it
+            // is re-initialized:
+            self.asm_state.faulted.set(true);
+            return Err(TockOGError::MPUConfigError);
+        }
+
+        Ok(())
+    }
+
+    // Whether the function at `idx` in the binary's function table has the
+    // given name. Names are stored as pointers to NUL-terminated strings,
+    // which must be contained in the binary:
+    fn fntab_name_matches(&self, idx: usize, name: &CStr) -> bool {
+        if idx >= self.fntab_length {
+            return false;
+        }
+
+        let name_ptr = unsafe { *(self.fntab_names_addr as *const *const u8).add(idx) };
+
+        // Compare the name including its NUL terminator:
+        let name_bytes = name.to_bytes_with_nul();
+        let flash_allocations = TockRv32iCRtAllocations {
+            ram_region_start: core::ptr::null_mut(),
+            ram_region_length: 0,
+            persistent_pool_length: 0,
+            flash_region_start: self.binary.binary_start as *mut (),
+            flash_region_length: self.binary.binary_length,
+        };
+
+        // The binary's name may be shorter than ours, in which case it would
+        // be NUL-terminated before. Thus, compare byte by byte:
+        name_bytes.iter().enumerate().all(|(offset, expected)| {
+            let byte_ptr = name_ptr.wrapping_add(offset);
+            flash_allocations.is_valid(byte_ptr as *const (), 1)
+                && unsafe { core::ptr::read_volatile(byte_ptr) } == *expected
+        })
+    }
+
+    /// Memory layout of the library, as configured in the MPU. Boards may
+    /// want to log this at boot.
+    pub fn memory_layout(&self) -> TockRv32iCMemoryLayout {
+        let mut layout = self.memory_layout;
+        if let Some((pool_start, pool_blocks)) = self.persistent_pool.get() {
+            layout.persistent_pool = Some(mpu::Region::new(
+                pool_start as *const u8,
+                pool_blocks * PERSISTENT_POOL_BLOCK_SIZE,
+            ));
+        }
+        layout
+    }
+
+    /// Heap usage of the library, as the number of bytes allocated through
+    /// its program break and the total size of its heap region. Returns
+    /// `None` if the library does not have a heap.
+    pub fn heap_usage(&self) -> Option<(usize, usize)> {
+        let (heap_start, heap_size, heap_brk_ptr) = self.heap?;
+
+        // The program break is part of foreign memory, so it can hold any
+        // value. We validated its location at construction:
+        let brk = unsafe { core::ptr::read_volatile(heap_brk_ptr) };
+
+        Some((
+            core::cmp::min(brk.saturating_sub(heap_start as usize), heap_size),
+            heap_size,
+        ))
+    }
+
+    /// Whether the runtime can accept a new invocation from the kernel main
+    /// loop, i.e., it is not executing, suspended or servicing interrupts.
+    pub fn is_idle(&self) -> bool {
+        !self.is_suspended()
+            && !self.asm_state.servicing_interrupts.get()
+            && self.asm_state.active_alloc_scope.get().is_null()
+    }
+
+    /// Whether a foreign invocation is currently suspended.
+    pub fn is_suspended(&self) -> bool {
+        self.asm_state.suspended.get()
+    }
+
+    /// Resume a suspended foreign invocation.
+    ///
+    /// Returns `Ok(None)` if the invocation was suspended again, or its
+    /// return value if it completed. As suspended invocations cannot return
+    /// values through the stack, `T` must fit into the two return registers.
+    pub fn resume<T>(
+        &self,
+        alloc_scope: &mut AllocScope<'_, TockRv32iCRtAllocChain<'_>, ID>,
+        access_scope: &mut AccessScope<ID>,
+    ) -> OGResult<Option<OGCopy<T>>> {
+        const {
+            assert!(core::mem::size_of::<T>() <= 2 * core::mem::size_of::<usize>());
+        }
+
+        if !self.is_suspended() {
+            return Err(OGError::InternalError);
+        }
+
+        let mut res: TockRv32iCInvokeRes<Self, T> = TockRv32iCInvokeRes::new();
+
+        self.execute(alloc_scope, access_scope, || unsafe {
+            Self::foreign_runtime_resume(self as *const _, &mut res.inner as *mut _)
+        });
+
+        match res.inner.error {
+            TockRv32iCInvokeErr::Suspended => Ok(None),
+            _ => res.into_result_registers(self).map(Some),
+        }
+    }
+
+    /// Provide a table of slots for callbacks registered through
+    /// `register_persistent_callback`.
+    ///
+    /// Callbacks registered in a previous table are no longer reachable.
+    pub fn set_persistent_callback_slots(
+        &self,
+        slots: &'static [TockRv32iCPersistentCallbackSlot<ID>],
+    ) {
+        self.persistent_callbacks.set(slots);
+    }
+
+    /// Register a callback that foreign code can call from any invocation of
+    /// this runtime, for as long as it remains registered.
+    ///
+    /// Returns a function pointer to pass to foreign code, which also serves
+    /// as a handle to `unregister_persistent_callback`. Callbacks remain
+    /// registered across `reset`. Returns `AllocNoMem` if there is no free
+    /// slot.
+    pub fn register_persistent_callback<C>(
+        &self,
+        callback: &'static C,
+    ) -> OGResult<*const CallbackTrampolineFn>
+    where
+        C: Fn(
+                &TockRv32iCRtCallbackContext,
+                &mut TockRv32iCRtCallbackReturn,
+                &mut AllocScope<'_, TockRv32iCRtAllocChain<'_>, ID>,
+                &mut AccessScope<ID>,
+            ) + 'static,
+    {
+        unsafe extern "C" fn persistent_callback_wrapper<
+            ID: OGID,
+            ClosureTy: Fn(
+                    &TockRv32iCRtCallbackContext,
+                    &mut TockRv32iCRtCallbackReturn,
+                    &mut AllocScope<'_, TockRv32iCRtAllocChain<'_>, ID>,
+                    &mut AccessScope<ID>,
+                ) + 'static,
+        >(
+            ctx_ptr: *mut c_void,
+            callback_ctx: &TockRv32iCRtCallbackContext,
+            callback_ret: &mut TockRv32iCRtCallbackReturn,
+            alloc_scope_ptr: *mut (),
+            access_scope_ptr: *mut (),
+        ) {
+            let closure: &'static ClosureTy = unsafe { &*(ctx_ptr as *const ClosureTy) };
+
+            let alloc_scope = unsafe {
+                &mut *(alloc_scope_ptr as *mut AllocScope<'_, TockRv32iCRtAllocChain<'_>, ID>)
+            };
+
+            let access_scope = unsafe { &mut *(access_scope_ptr as *mut AccessScope<ID>) };
+
+            // For now, we assume that the function doesn't unwind:
+            closure(callback_ctx, callback_ret, alloc_scope, access_scope)
+        }
+
+        let slot = self
+            .persistent_callbacks
+            .get()
+            .iter()
+            .find(|slot| slot.callback.get().is_none())
+            .ok_or(OGError::AllocNoMem)?;
 
-        // Create an MPU configuration that sets up appropriate permissions for
-        // the Omniglot binary:
-        let mut mpu_config = mpu
-            .new_config()
-            .ok_or_else(|| TockOGError::MPUConfigError)?;
+        slot.callback.set(Some((
+            persistent_callback_wrapper::<ID, C>,
+            callback as *const C as *mut c_void,
+        )));
 
-        mpu.allocate_region(
-            binary.binary_start as *const u8,
-            binary.binary_length,
-            binary.binary_length,
-            mpu::Permissions::ReadExecuteOnly,
-            &mut mpu_config,
-        )
-        .unwrap();
+        Ok(slot.springboard_ptr())
+    }
 
-        mpu.allocate_region(
-            ram_region_start as *mut u8 as *const _,
-            ram_region_length,
-            ram_region_length,
-            mpu::Permissions::ReadWriteOnly,
-            &mut mpu_config,
-        )
-        .unwrap();
+    /// Unregister a callback registered through
+    /// `register_persistent_callback`. Foreign code calling it afterwards
+    /// faults.
+    pub fn unregister_persistent_callback(&self, callback_ptr: *const CallbackTrampolineFn) {
+        if let Some(slot) = self
+            .persistent_callbacks
+            .get()
+            .iter()
+            .find(|slot| slot.springboard_ptr() == callback_ptr)
+        {
+            slot.callback.set(None);
+        }
+    }
 
-        for (region, permissions) in addl_mpu_regions {
-            mpu.allocate_region(
-                region.start_address(),
-                region.size(),
-                region.size(),
-                permissions,
-                &mut mpu_config,
-            )
-            .unwrap();
+    fn find_persistent_callback(&self, mepc: usize) -> Option<(CallbackWrapperFn, *mut c_void)> {
+        self.persistent_callbacks
+            .get()
+            .iter()
+            .find(|slot| slot.springboard_ptr() as usize == mepc)
+            .and_then(|slot| slot.callback.get())
+    }
+
+    /// Provide host calls to this runtime, as a table of host-call numbers
+    /// and the services implementing them.
+    ///
+    /// Foreign code issuing a host call not contained in this table receives
+    /// `HOST_CALL_ERR_NOSYS` in `a0`.
+    pub fn set_host_calls(
+        &self,
+        host_calls: &'static [(usize, &'static dyn TockRv32iCHostCallService)],
+    ) {
+        self.host_calls.set(host_calls);
+    }
+
+    fn dispatch_host_call(
+        &self,
+        num: usize,
+        args: [usize; 6],
+        alloc_tracker: &TockRv32iCRtAllocChain<'_>,
+    ) -> (usize, usize) {
+        self.host_calls
+            .get()
+            .iter()
+            .find(|(host_call_num, _)| *host_call_num == num)
+            .map_or((HOST_CALL_ERR_NOSYS, 0), |(_, service)| {
+                service.host_call(num, args, alloc_tracker)
+            })
+    }
+
+    /// Service kernel interrupts while foreign code is running.
+    ///
+    /// When set, every interrupt that preempts foreign code invokes
+    /// `service`, after which the foreign function is resumed. Otherwise,
+    /// interrupts are masked until the outermost invocation returns.
+    pub fn set_interrupt_service(&self, service: Option<&'static dyn OmniglotInterruptService>) {
+        self.interrupt_service.set(service);
+    }
+
+    /// The maximum latency, in CPU cycles, that running foreign code has
+    /// added to the handling of a kernel interrupt.
+    ///
+    /// This is measured from the runtime's interrupt trap handler to when the
+    /// kernel services interrupts again, either by returning from the
+    /// invocation, or through the interrupt service set with
+    /// `set_interrupt_service`.
+    pub fn max_interrupt_latency_cycles(&self) -> usize {
+        self.max_interrupt_latency_cycles.get()
+    }
+
+    pub fn reset_max_interrupt_latency(&self) {
+        self.max_interrupt_latency_cycles.set(0);
+    }
+
+    fn record_interrupt_latency(&self, since: usize) {
+        let latency = Self::read_mcycle().wrapping_sub(since);
+        if latency > self.max_interrupt_latency_cycles.get() {
+            self.max_interrupt_latency_cycles.set(latency);
         }
+    }
 
-        // Construct an initial runtime instance. We don't yet know where our
-        // `foreign_stack_top` should be placed -- that will depend on how much
-        // static data `init` will place at the top of memory. We need to set
-        // the stack pointer equal to some valid value though, and thus we --
-        // for now -- set it to be nthe top of memory.
-        let ram_region_end = unsafe { ram_region_start.byte_add(ram_region_length) };
+    #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+    fn read_mcycle() -> usize {
+        let mcycle: usize;
+        unsafe {
+            core::arch::asm!("csrr {0}, mcycle", out(reg) mcycle);
+        }
+        mcycle
+    }
 
-        let rt = TockRv32iCRt {
-            asm_state: TockRv32iCRtAsmState {
-                foreign_stack_ptr: Cell::new(ram_region_end),
-                foreign_stack_bottom: ram_region_start,
-                ram_region_start,
-                ram_region_length,
-                active_alloc_scope: Cell::new(core::ptr::null_mut()),
-                mpu: mpu as *const _ as *const _,
-            },
+    #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+    fn read_mcycle() -> usize {
+        0
+    }
 
-            binary,
-            rthdr_addr,
-            init_addr,
-            fntab_addr,
-            fntab_length,
+    // Read the `mepc` and `mstatus` CSRs of the current trap context:
+    #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+    fn read_trap_csrs() -> (usize, usize) {
+        let (mepc, mstatus): (usize, usize);
+        unsafe {
+            core::arch::asm!(
+                "csrr {0}, mepc",
+                "csrr {1}, mstatus",
+                out(reg) mepc,
+                out(reg) mstatus,
+            );
+        }
+        (mepc, mstatus)
+    }
 
-            mpu,
-            mpu_config,
+    #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+    fn read_trap_csrs() -> (usize, usize) {
+        (0, 0)
+    }
 
-            _id: PhantomData::<ID>,
-        };
+    // Restore the `mepc` and `mstatus` CSRs read through `read_trap_csrs`:
+    #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+    unsafe fn restore_trap_csrs((mepc, mstatus): (usize, usize)) {
+        unsafe {
+            core::arch::asm!(
+                "csrw mepc, {0}",
+                "csrw mstatus, {1}",
+                in(reg) mepc,
+                in(reg) mstatus,
+            );
+        }
+    }
 
-        let mut alloc_scope = unsafe {
-            AllocScope::new(
-                TockRv32iCRtAllocChain::BaseAllocations(TockRv32iCRtAllocations {
-                    ram_region_start,
-                    ram_region_length,
-                    flash_region_start: binary.binary_start as *const _ as *mut (),
-                    flash_region_length: binary.binary_length,
-                }),
-                ogid.get_imprint(),
-            )
-        };
-        rt.asm_state
+    #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+    unsafe fn restore_trap_csrs(_csrs: (usize, usize)) {}
+
+    // Perform a restart requested by the fault policy, if any, and if the
+    // policy permits it now. This must only be called from an outermost
+    // `execute`, with its `alloc_scope` set as the active scope.
+    fn restart_if_pending(&self) {
+        if !self.restart_pending.get() || !self.fault_policy.may_restart(self.restart_count.get()) {
+            return;
+        }
+
+        self.asm_state.faulted.set(false);
+        self.fault.set(None);
+        self.restart_pending.set(false);
+        self.restart_error.set(None);
+        self.restart_count.set(self.restart_count.get() + 1);
+
+        // The caller of `execute` may hold stacked allocations. Those are
+        // placed below the stack top reported by `init`, so retain the
+        // current foreign stack pointer:
+        let fsp = self.asm_state.foreign_stack_ptr.get();
+        let res = self.init();
+        self.asm_state.foreign_stack_ptr.set(fsp);
+
+        // Should `init` fault, the fault policy has already been consulted in
+        // `encode_return`. An error returned by `init` leaves the library in
+        // the same state as a fault, so we consult the policy here:
+        if let Err(TockOGError::LibraryInitFailed { error }) = res {
+            match self.fault_policy.action(self.restart_count.get()) {
+                OmniglotFaultAction::Panic => {
+                    panic!("Library failed to re-initialize: {:08x}", error)
+                }
+                OmniglotFaultAction::Stop => (),
+                OmniglotFaultAction::Restart => self.restart_pending.set(true),
+            }
+        }
+
+        self.restart_error.set(res.err());
+    }
+
+    fn base_allocations(&self) -> TockRv32iCRtAllocations {
+        TockRv32iCRtAllocations {
+            ram_region_start: self.asm_state.ram_region_start,
+            ram_region_length: self.asm_state.ram_region_length,
+            persistent_pool_length: self.persistent_pool.get().map_or(0, |(pool_start, _)| {
+                self.asm_state.ram_region_start as usize + self.asm_state.ram_region_length
+                    - pool_start as usize
+            }),
+            flash_region_start: self.binary.binary_start as *const _ as *mut (),
+            flash_region_length: self.binary.binary_length,
+        }
+    }
+
+    fn init_with_alloc_scope(
+        &self,
+        alloc_scope: &mut AllocScope<'_, TockRv32iCRtAllocChain<'_>, ID>,
+    ) -> Result<(), TockOGError> {
+        self.asm_state
             .active_alloc_scope
-            .set(&mut alloc_scope as *mut _ as *mut ());
+            .set(alloc_scope as *mut _ as *mut ());
 
-        rt.init()?;
+        let res = self.init();
 
         // Reset the active scope to force a null-pointer exception for
         // generic_invoke executions that don't pass through `execute`:
-        rt.asm_state.active_alloc_scope.set(core::ptr::null_mut());
+        self.asm_state.active_alloc_scope.set(core::ptr::null_mut());
 
-        Ok((rt, alloc_scope, unsafe {
-            AccessScope::new(ogid.get_imprint())
-        }))
+        res
+    }
+
+    /// Information on the fault of a foreign function, if any.
+    ///
+    /// Once foreign code has faulted, the runtime refuses to perform any
+    /// further invocations, which instead return an error, until it is
+    /// re-initialized through `reset` or restarted by its fault policy.
+    pub fn fault(&self) -> Option<TockRv32iCFault> {
+        self.fault.get()
     }
 
-    fn init(&self) -> OGResult<OGCopy<()>> {
+    fn init(&self) -> Result<(), TockOGError> {
         let mut res = TockRv32iCInvokeRes::new();
 
         //kernel::debug!("Initializing foreign runtime, ptr: {:?}, init addr: {:p}", self.rthdr_addr, self.init_addr);
 
-        self.execute_int_configure_mpu(|| unsafe {
-            Self::foreign_runtime_init(
-                self.rthdr_addr as usize,
-                0,
-                0,
-                0,
-                0,
-                self as *const _,
-                self.init_addr,
-                &mut res as *mut _,
-            )
+        // `init` is not run through `execute`, and thus never suspended. We
+        // must retrieve the stack pointer that it returns:
+        self.with_invoke_budget(|| {
+            self.execute_int_configure_mpu(|| unsafe {
+                Self::foreign_runtime_init(
+                    self.rthdr_addr as usize,
+                    0,
+                    0,
+                    0,
+                    0,
+                    self as *const _,
+                    self.init_addr,
+                    &mut res as *mut _,
+                )
+            })
         });
 
         res.encode_ogerror()?;
 
-        // Function did not fault. Check whether it returned an error though:
+        // Function did not fault. Check whether it returned an error though.
+        // The library is then in an undefined state, so we refuse all further
+        // invocations, as we would after a fault:
         if res.inner.a0 != 0 {
-            panic!("Function returned error: {:08x}", res.inner.a0);
+            self.asm_state.faulted.set(true);
+            return Err(TockOGError::LibraryInitFailed {
+                error: res.inner.a0,
+            });
         }
 
         // Function initialized successfully. It provides us with a new stack pointer that we are
@@ -512,15 +2036,68 @@ impl<ID: OGID, M: MPU + 'static> TockRv32iCRt<ID, M> {
             .foreign_stack_ptr
             .set(res.inner.sp as *mut ());
 
-        Ok(OGCopy::new(()))
+        Ok(())
+    }
+
+    // Run `f` under this runtime's execution-time budget, if any. This must
+    // only be used for outermost invocations of foreign code:
+    fn with_invoke_budget<R, F: FnOnce() -> R>(&self, f: F) -> R {
+        let invoke_budget = self.invoke_budget.get();
+
+        if let Some((timer, budget_us)) = invoke_budget {
+            timer.reset();
+            timer.start(budget_us);
+            timer.arm();
+            self.invoke_budget_armed.set(true);
+        }
+
+        let res = f();
+
+        if let Some((timer, _)) = invoke_budget {
+            self.invoke_budget_armed.set(false);
+            timer.disarm();
+            timer.reset();
+        }
+
+        res
+    }
+
+    // Interrupts taken while executing kernel code on behalf of foreign code
+    // (e.g., callbacks) are handled by the kernel's trap handler, which
+    // disables them. Re-arm the execution-time budget timer before resuming
+    // foreign code, such that an expired budget preempts it immediately:
+    fn rearm_invoke_budget(&self) {
+        if self.invoke_budget_armed.get() {
+            if let Some((timer, _)) = self.invoke_budget.get() {
+                timer.arm();
+            }
+        }
+    }
+
+    // Load this runtime's MPU configuration.
+    //
+    // The configuration is only ever borrowed for the duration of a single
+    // MPU driver call, so it is never taken here. If it were, we would run
+    // foreign code with whichever regions happen to be loaded, which is
+    // unsound. Thus, we panic in this case:
+    fn configure_mpu(&self) {
+        if self
+            .mpu_config
+            .map(|mpu_config| self.mpu.configure_mpu(mpu_config))
+            .is_none()
+        {
+            panic!("Omniglot runtime MPU configuration is unavailable");
+        }
     }
 
     fn execute_int_configure_mpu<R, F: FnOnce() -> R>(&self, f: F) -> R {
-        self.mpu.configure_mpu(&self.mpu_config);
+        self.configure_mpu();
         self.mpu.enable_app_mpu();
 
         let res = f();
 
+        // This configuration is not restored for processes: the kernel
+        // re-applies a process' configuration before switching to it.
         self.mpu.disable_app_mpu();
 
         res
@@ -569,6 +2146,38 @@ impl<ID: OGID, M: MPU + 'static> TockRv32iCRt<ID, M> {
         );
     }
 
+    #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+    unsafe extern "C" fn foreign_runtime_resume(
+        _a0_rt: *const Self,
+        _a1_res: *mut TockRv32iCInvokeResInner,
+    ) {
+        unimplemented!();
+    }
+
+    #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+    #[unsafe(naked)]
+    unsafe extern "C" fn foreign_runtime_resume(
+        _a0_rt: *const Self,
+        _a1_res: *mut TockRv32iCInvokeResInner,
+    ) {
+        core::arch::naked_asm!(
+            "
+                // Load required parameters in non-argument registers and
+                // continue execution in the generic protection-domain
+                // switch routine. The function pointer is ignored, as we
+                // resume from the saved program counter:
+                mv   t0, a0                 // Load runtime pointer
+                li   t1, 0                  // Load function pointer
+                mv   t2, a1                 // Load the InvokeRes pointer
+                li   t3, 0                  // Load the stack-spill immediate
+                li   t5, -2                 // Load a marker indicating a resume request
+                la   t4, {invoke_sym}       // Load the generic_invoke function
+                jr   t4                     // Tail-call into the invoke fn
+            ",
+            invoke_sym = sym Self::generic_invoke,
+        );
+    }
+
     #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
     unsafe extern "C" fn generic_invoke() {
         unimplemented!();
@@ -577,6 +2186,8 @@ impl<ID: OGID, M: MPU + 'static> TockRv32iCRt<ID, M> {
         #[allow(unreachable_code)]
         {
             let _: _ = Self::encode_return;
+            let _: _ = Self::encode_refused;
+            let _: _ = Self::invoke_preempted;
         }
     }
 
@@ -607,30 +2218,33 @@ impl<ID: OGID, M: MPU + 'static> TockRv32iCRt<ID, M> {
             mepc_reg = out(reg) mepc
         );
 
-        // ecall -> function return
-        let ecall_function_return = mcause == MCAUSE_ENV_CALL_UMODE;
-
-        // instruction access fault at return springboard -> function return
-        let iaf_function_return = mcause == MCAUSE_INSTRUCTION_ACCESS_FAULT
+        // ecall or instruction access fault at return springboard -> function
+        // return
+        let function_return = (mcause == MCAUSE_ENV_CALL_UMODE
+            || mcause == MCAUSE_INSTRUCTION_ACCESS_FAULT)
             && mepc == og_tock_rv32i_c_rt_ret_springboard as usize;
 
-        let function_return = ecall_function_return || iaf_function_return;
+        // ecall anywhere else -> host call
+        let host_call = mcause == MCAUSE_ENV_CALL_UMODE && !function_return;
 
         // callbacks must be triggered through an instruction access fault or an
         // ILLEGAL_INSTRUCTION exception:
         let callback_fault =
             mcause == MCAUSE_INSTRUCTION_ACCESS_FAULT || mcause == MCAUSE_ILLEGAL_INSTRUCTION;
 
-        if function_return || !callback_fault {
+        if function_return || !(callback_fault || host_call) {
             // Either a function return or other non-callback fault, return:
             return 0;
         }
 
-        // This _is_ a callback, handle it!
+        // This _is_ a callback or host call, handle it!
 
         let callback_asm_ctx = &mut *callback_asm_ctx_ptr;
         let runtime = &*callback_asm_ctx.runtime;
 
+        // The asm state is placed at the beginning of the runtime struct:
+        let rt = &*(callback_asm_ctx.runtime as *const Self);
+
         // Disable the app MPU:
         let mpu = &*(runtime.mpu as *const M);
         mpu.disable_app_mpu();
@@ -665,14 +2279,26 @@ impl<ID: OGID, M: MPU + 'static> TockRv32iCRt<ID, M> {
                         cur = pred;
                     }
                 }
+                TockRv32iCRtAllocChain::PersistentAllocation(_, _, pred) => {
+                    cur = pred;
+                }
+                TockRv32iCRtAllocChain::Grant(_, _, _, pred) => {
+                    cur = pred;
+                }
                 TockRv32iCRtAllocChain::Cons(pred) => {
                     cur = pred;
                 }
             }
         };
 
-        let callback_desc = if let Some(desc) = callback_desc {
-            desc
+        // Otherwise, check whether this is a persistent callback. Host calls
+        // are not dispatched through a callback (`None`):
+        let callback = if host_call {
+            None
+        } else if let Some(desc) = callback_desc {
+            Some((desc.wrapper, desc.context))
+        } else if let Some(callback) = rt.find_persistent_callback(mepc) {
+            Some(callback)
         } else {
             // This is not a callback invocation, proceed returning to the kernel:
             return 0;
@@ -681,33 +2307,85 @@ impl<ID: OGID, M: MPU + 'static> TockRv32iCRt<ID, M> {
         // Construct a CallbackContext from the arguments to this function:
         let callback_ctx = TockRv32iCRtCallbackContext {
             arg_regs: [a0, a1, a2, a3, a4, a5, a6, a7],
+            foreign_stack_ptr: callback_asm_ctx.foreign_stack_ptr as *const (),
         };
 
         // Construct a default CallbackReturn:
         let mut callback_ret = TockRv32iCRtCallbackReturn { ret_regs: [0; 2] };
 
-        // Execute the interrupt handler function.
-        //
-        // TODO: In the future, we should transition this out of the trap
-        // handler to allow for nested domain switches.
+        // The callback may invoke foreign functions, of this or other
+        // runtimes. Each such invocation saves the kernel context in a new
+        // frame on the kernel stack, below this handler, and restores
+        // `mscratch` to zero before returning to us. However, nested
+        // invocations of this runtime must not clobber the stack of the
+        // function that issued this callback. Thus, continue allocating
+        // below its (aligned) stack pointer for the duration of the callback:
+        let prev_foreign_stack_ptr = runtime.foreign_stack_ptr.get();
+        runtime
+            .foreign_stack_ptr
+            .set(((callback_asm_ctx.foreign_stack_ptr as usize) & !15) as *mut ());
+        rt.callback_depth.set(rt.callback_depth.get() + 1);
+
+        // Execute the callback handler function, or the host call:
         let mut inner_alloc_scope: AllocScope<'_, TockRv32iCRtAllocChain<'_>, ID> = AllocScope::new(
             TockRv32iCRtAllocChain::Cons(alloc_scope.tracker()),
             alloc_scope.id_imprint(),
         );
 
-        callback_desc.invoke(
-            &callback_ctx,
-            &mut callback_ret,
-            &mut inner_alloc_scope as *mut _ as *mut (),
-            // Safe, as this should only be triggered by foreign code, when the only
-            // existing AccessScope<ID> is already borrowed by the trampoline:
-            &mut AccessScope::<ID>::new(alloc_scope.id_imprint()) as *mut _ as *mut (),
-        );
+        if let Some((callback_wrapper, callback_context)) = callback {
+            (callback_wrapper)(
+                callback_context,
+                &callback_ctx,
+                &mut callback_ret,
+                &mut inner_alloc_scope as *mut _ as *mut (),
+                // Safe, as this should only be triggered by foreign code, when the only
+                // existing AccessScope<ID> is already borrowed by the trampoline:
+                &mut AccessScope::<ID>::new(alloc_scope.id_imprint()) as *mut _ as *mut (),
+            );
+        } else {
+            // The host-call number is passed in a7, followed by up to six
+            // arguments in a0 -- a5:
+            let (ret_a0, ret_a1) =
+                rt.dispatch_host_call(a7, [a0, a1, a2, a3, a4, a5], inner_alloc_scope.tracker());
+            callback_ret.ret_regs = [ret_a0, ret_a1];
+
+            // Resume after the (uncompressed) ecall instruction:
+            callback_asm_ctx.ret_pc = mepc + 4;
+        }
+
+        rt.callback_depth.set(rt.callback_depth.get() - 1);
+        runtime.foreign_stack_ptr.set(prev_foreign_stack_ptr);
 
         callback_asm_ctx.ret_a0 = callback_ret.ret_regs[0];
         callback_asm_ctx.ret_a1 = callback_ret.ret_regs[1];
 
-        // Re-enable the app MPU:
+        // A nested invocation returns from its own trap context, leaving
+        // interrupts enabled and mstatus.MPP set to machine mode. Restore the
+        // state expected by our trap handler: interrupts disabled, and
+        // returning to user-mode with interrupts enabled on mret.
+        core::arch::asm!(
+            "
+                csrc  mstatus, {mie}
+                csrc  mstatus, {mpp}
+                csrs  mstatus, {mpie}
+            ",
+            mie = in(reg) 0x00000008,
+            mpp = in(reg) 0x00001800,
+            mpie = in(reg) 0x00000080,
+        );
+
+        // If a nested invocation of this runtime faulted or was aborted, the
+        // state of the function that issued this callback is undefined.
+        // Abort it by returning to the kernel:
+        if runtime.faulted.get() {
+            return 0;
+        }
+
+        rt.rearm_invoke_budget();
+
+        // Re-enable the app MPU. Nested invocations of other runtimes may
+        // have changed its configuration, so restore ours:
+        rt.configure_mpu();
         mpu.enable_app_mpu();
 
         // This was a callback!
@@ -744,6 +2422,8 @@ impl<ID: OGID, M: MPU + 'static> TockRv32iCRt<ID, M> {
                 // - t2: &mut TockRv32iCRtInvokeResInner
                 // - t3: amount of bytes spilled on the stack, to copy
                 // - t4: <this function symbol>
+                // - t5: marker indicating the source of this call, where -2
+                //       requests to resume a suspended invocation
                 //
                 // As this symbol follows the C ABI / calling convention, and we
                 // cannot rely on the foreign code to preserve saved registers,
@@ -752,12 +2432,18 @@ impl<ID: OGID, M: MPU + 'static> TockRv32iCRt<ID, M> {
                 // Following that, we need to copy the stack-spilled arguments
                 // onto the foreign stack and switch to user-mode.
                 //
+                // If the runtime has faulted previously, we must not execute
+                // any foreign code. Instead, we tail-call into a function that
+                // encodes this error into the InvokeRes (see `900` below).
+                //
                 // The trap handler may return to this code because either
                 //
                 // - we received an interrupt while executing foreign code.
                 //   In this case, disable the interrupt, and resume execution
                 //   of foreign code, being careful to not clobber any
-                //   registers.
+                //   registers. If the invocation has exceeded its
+                //   execution-time budget, we instead abort or suspend it and
+                //   return to the kernel.
                 //
                 // - we received a system call (return instruction), or a trap.
                 //   It is not possible for us to modify the foreign binary and
@@ -774,6 +2460,33 @@ impl<ID: OGID, M: MPU + 'static> TockRv32iCRt<ID, M> {
                 //   All other traps are faults and should require
                 //   re-initialization of the Omniglot runtime.
 
+                // Check whether this runtime has faulted. We can clobber `t4`,
+                // it only holds the address of this function:
+                lbu t4, {rtas_faulted_offset}(t0)
+                bnez t4, 900f
+
+                // Refuse any invocations while servicing interrupts from
+                // within a preempted function of this runtime:
+                lbu t4, {rtas_servicing_interrupts_offset}(t0)
+                bnez t4, 900f
+
+                // If an invocation is suspended, we must only accept a resume
+                // request, and vice versa. Keep the result of this check in
+                // `t6` (1 iff resuming), which is not used until we switch to
+                // the foreign function:
+                lbu t4, {rtas_suspended_offset}(t0)
+                addi t6, t5, 2      // t6 = 0 iff t5 == -2
+                seqz t6, t6         // t6 = 1 iff t5 == -2
+                bne t4, t6, 900f
+
+                // Arguments passed on the stack are copied below the foreign
+                // stack pointer, which is reset once this invocation returns.
+                // Thus, invocations with stacked arguments must not be
+                // suspended:
+                beqz t3, 100f
+                sb   x0, {rtas_suspendable_offset}(t0)
+              100: // _suspendable_checked
+
                 // First, save the current stack pointer in a temporary
                 // register. We start copying foreign arguments from this point
                 // onward in a bit.
@@ -876,6 +2589,10 @@ impl<ID: OGID, M: MPU + 'static> TockRv32iCRt<ID, M> {
 
                 // sw x0, 0*4(sp)   // Reserved as scratch space for trap handler
 
+                // When resuming a suspended invocation, there are no arguments
+                // to copy. Instead, restore the saved register file:
+                bnez  t6, 950f
+
                 // Now, copy the stacked arguments. For this we need to:
                 //
                 // 1. load the current foreign stack pointer,
@@ -1047,8 +2764,9 @@ impl<ID: OGID, M: MPU + 'static> TockRv32iCRt<ID, M> {
                 bge   s1, x0, 700f
 
                 // This was an interrupt! We save all callee-saved registers
-                // and call the function to disable interrupts. We then proceed
-                // executing the application.
+                // and call the function to disable interrupts. If configured,
+                // `invoke_preempted` then services pending interrupts and
+                // deferred calls. We then proceed executing the application.
 
                 // First, save the foreign stack pointer onto our stack:
                 sw    x2, 35*4(s0)
@@ -1094,6 +2812,20 @@ impl<ID: OGID, M: MPU + 'static> TockRv32iCRt<ID, M> {
                 mv   a0, s1     // a0 = s1 (mcause)
                 jal  ra, _disable_interrupt_trap_rust_from_app
 
+                // Check whether this invocation has exceeded its
+                // execution-time budget. If so, abort (a0 = 1) or suspend
+                // (a0 = 2) it, returning to the kernel through the regular
+                // return path. `s1` is callee-saved and thus still contains
+                // mcause, which allows `encode_return` to identify this as a
+                // timeout. We have already restored the kernel trap handler
+                // and saved all foreign caller-saved registers, as expected
+                // by `_return_to_kernel`:
+                lw   a0, 3*4(sp) // a0 = &TockRv32iCRtAsmState
+                jal  ra, {invoke_preempted_sym}
+                li   t0, 1
+                beq  a0, t0, 850f
+                bnez a0, 860f
+
                 // Restore registers from the stack:
                 lw    x1, 19*4(sp) // ra
                 lw    x5, 20*4(sp) // t0
@@ -1144,7 +2876,9 @@ impl<ID: OGID, M: MPU + 'static> TockRv32iCRt<ID, M> {
                 // Handling a callback is simpler. In that case, we can simply
                 // invoke a C-ABI callback handler and assume that the app
                 // has already saved all required registers. We thus call this
-                // function here, in the context of the trap handler.
+                // function here, in the context of the trap handler. The
+                // callback may itself invoke foreign functions, which push
+                // their own frames onto the kernel stack below ours.
                 //
                 // To do so, we need to restore a couple of registers and
                 // populate a CallbackAsmContext struct that we pass to the
@@ -1184,6 +2918,7 @@ impl<ID: OGID, M: MPU + 'static> TockRv32iCRt<ID, M> {
                 sw    t0, ({callback_ctx_foreign_stack_ptr_offset} + 4)(sp)
                 lw    t0, ({callback_ctx_ptr_size} + 3*4)(sp)
                 sw    t0, ({callback_ctx_runtime_offset} + 4)(sp)
+                sw    x1, ({callback_ctx_ret_pc_offset} + 4)(sp) // foreign ra
 
                 addi  t0, sp, 4
                 sw    t0, 0*4(sp)
@@ -1196,14 +2931,17 @@ impl<ID: OGID, M: MPU + 'static> TockRv32iCRt<ID, M> {
                 jal   ra, {callback_handler}
 
                 // Check if this callback was successfully handled.
-                beqz  a0, 800f     // not a callback, return.
+                beqz  a0, 800f     // not a callback or aborted, return.
 
                 // Return from the callback. For this, we must restore the saved registers
                 // above and load the return value registers prepared by the callback handler.
                 //
-                // Load the return values into a0 and a1:
+                // Load the return values into a0 and a1, and the address to
+                // resume foreign code at into t0. Foreign code expects t0 to
+                // be clobbered, as for any function call:
                 lw    a0, ({callback_ctx_ret_a0_offset} + 4)(sp)
                 lw    a1, ({callback_ctx_ret_a1_offset} + 4)(sp)
+                lw    t0, ({callback_ctx_ret_pc_offset} + 4)(sp)
 
                 // Pop the CallbackAsmContext stack frame:
                 addi  sp, sp, {callback_ctx_ptr_size}
@@ -1213,8 +2951,10 @@ impl<ID: OGID, M: MPU + 'static> TockRv32iCRt<ID, M> {
                 lw    x3, 36*4(sp) // foreign gp
                 lw    x1, 19*4(sp) // foreign ra
 
-                // Load the app's return address register (in ra / x1) into mepc:
-                csrw  mepc, x1     // foreign ra, return to requested return address
+                // Load the resume address into mepc. For callbacks, this is
+                // the foreign return address register (ra / x1), for host
+                // calls the instruction following the ecall:
+                csrw  mepc, t0     // return to requested return address
 
                 // Reset the trap handler by switching our kernel stack into
                 // `mscratch` again. We discard its current value, which must
@@ -1247,6 +2987,8 @@ impl<ID: OGID, M: MPU + 'static> TockRv32iCRt<ID, M> {
                 // Pop the CallbackAsmContext stack frame:
                 addi  sp, sp, {callback_ctx_ptr_size}
 
+              850: // _return_to_kernel
+
                 // Restore saved application registers:
                 lw   x11, 24*4(sp) // a1 (return value)
                 lw   x10, 23*4(sp) // a0 (return value)
@@ -1317,14 +3059,156 @@ impl<ID: OGID, M: MPU + 'static> TockRv32iCRt<ID, M> {
                 // the return value.
                 mret
 
+              860: // _suspend_foreign
+                // Save the full register file of the preempted foreign
+                // function into the runtime, such that it can be resumed
+                // later. Foreign callee-saved registers (including `s0`) are
+                // still live, as are the CSRs describing this trap. All other
+                // registers are saved on our stack. We may clobber `t0` and
+                // `t1`, as they are saved too:
+                lw    t0, 3*4(sp)   // t0 = &TockRv32iCRtAsmState
+                addi  t0, t0, {rtas_suspended_regs_offset}
+
+                csrr  t1, mepc
+                sw    t1,  0*4(t0)  // pc
+                lw    t1, 19*4(sp)
+                sw    t1,  1*4(t0)  // ra
+                lw    t1, 35*4(sp)
+                sw    t1,  2*4(t0)  // sp
+                lw    t1, 36*4(sp)
+                sw    t1,  3*4(t0)  // gp
+                lw    t1, 37*4(sp)
+                sw    t1,  4*4(t0)  // tp
+                lw    t1, 20*4(sp)
+                sw    t1,  5*4(t0)  // t0
+                lw    t1, 21*4(sp)
+                sw    t1,  6*4(t0)  // t1
+                lw    t1, 22*4(sp)
+                sw    t1,  7*4(t0)  // t2
+                sw    x8,  8*4(t0)  // s0 / fp
+                lw    t1,  0*4(sp)
+                sw    t1,  9*4(t0)  // s1
+                lw    t1, 23*4(sp)
+                sw    t1, 10*4(t0)  // a0
+                lw    t1, 24*4(sp)
+                sw    t1, 11*4(t0)  // a1
+                lw    t1, 25*4(sp)
+                sw    t1, 12*4(t0)  // a2
+                lw    t1, 26*4(sp)
+                sw    t1, 13*4(t0)  // a3
+                lw    t1, 27*4(sp)
+                sw    t1, 14*4(t0)  // a4
+                lw    t1, 28*4(sp)
+                sw    t1, 15*4(t0)  // a5
+                lw    t1, 29*4(sp)
+                sw    t1, 16*4(t0)  // a6
+                lw    t1, 30*4(sp)
+                sw    t1, 17*4(t0)  // a7
+                sw   x18, 18*4(t0)  // s2
+                sw   x19, 19*4(t0)  // s3
+                sw   x20, 20*4(t0)  // s4
+                sw   x21, 21*4(t0)  // s5
+                sw   x22, 22*4(t0)  // s6
+                sw   x23, 23*4(t0)  // s7
+                sw   x24, 24*4(t0)  // s8
+                sw   x25, 25*4(t0)  // s9
+                sw   x26, 26*4(t0)  // s10
+                sw   x27, 27*4(t0)  // s11
+                lw    t1, 31*4(sp)
+                sw    t1, 28*4(t0)  // t3
+                lw    t1, 32*4(sp)
+                sw    t1, 29*4(t0)  // t4
+                lw    t1, 33*4(sp)
+                sw    t1, 30*4(t0)  // t5
+                lw    t1, 34*4(sp)
+                sw    t1, 31*4(t0)  // t6
+
+                // Return to the kernel. `encode_return` will find the runtime
+                // marked as suspended:
+                j     850b
+
+              950: // _resume_foreign
+                // Resume a suspended invocation. We have saved the kernel's
+                // registers and set up the trap handler address as for any
+                // other invocation. Now, clear the suspended flag and switch
+                // to user-mode, as in `_stack_copied`:
+                sb    x0, {rtas_suspended_offset}(t0)
+
+                li    s1, 0x00001808
+                csrc  mstatus, s1         // clear MIE and MPP bits in mstatus
+                li    s1, 0x00000080
+                csrs  mstatus, s1         // set MPIE bit in mstatus
+
+                csrw  mscratch, sp        // Store `sp` in mscratch CSR
+
+                // Restore the foreign register file, using `s0` as the base
+                // pointer, which is restored last:
+                addi  s0, t0, {rtas_suspended_regs_offset}
+
+                lw    s1,  0*4(s0)
+                csrw  mepc, s1            // Resume at the saved pc
+
+                lw    x1,  1*4(s0)  // ra
+                lw    x2,  2*4(s0)  // sp
+                lw    x3,  3*4(s0)  // gp
+                lw    x4,  4*4(s0)  // tp
+                lw    x5,  5*4(s0)  // t0
+                lw    x6,  6*4(s0)  // t1
+                lw    x7,  7*4(s0)  // t2
+                lw    x9,  9*4(s0)  // s1
+                lw   x10, 10*4(s0)  // a0
+                lw   x11, 11*4(s0)  // a1
+                lw   x12, 12*4(s0)  // a2
+                lw   x13, 13*4(s0)  // a3
+                lw   x14, 14*4(s0)  // a4
+                lw   x15, 15*4(s0)  // a5
+                lw   x16, 16*4(s0)  // a6
+                lw   x17, 17*4(s0)  // a7
+                lw   x18, 18*4(s0)  // s2
+                lw   x19, 19*4(s0)  // s3
+                lw   x20, 20*4(s0)  // s4
+                lw   x21, 21*4(s0)  // s5
+                lw   x22, 22*4(s0)  // s6
+                lw   x23, 23*4(s0)  // s7
+                lw   x24, 24*4(s0)  // s8
+                lw   x25, 25*4(s0)  // s9
+                lw   x26, 26*4(s0)  // s10
+                lw   x27, 27*4(s0)  // s11
+                lw   x28, 28*4(s0)  // t3
+                lw   x29, 29*4(s0)  // t4
+                lw   x30, 30*4(s0)  // t5
+                lw   x31, 31*4(s0)  // t6
+                lw    x8,  8*4(s0)  // s0 / fp, last
+
+                // Continue executing the foreign function, re-enabling
+                // interrupts:
+                mret
+
+              900: // _refuse_invoke
+                // The runtime has faulted previously, or is in a state where
+                // it cannot accept this invocation. We have not modified any
+                // state yet, so simply tail-call into the function encoding
+                // this error, passing the runtime and InvokeRes pointers. The
+                // return address still points to our caller:
+                mv    a0, t0        // a0 = &TockRv32iCRtAsmState
+                mv    a1, t2        // a1 = &mut TockRv32iCRtInvokeResInner
+                tail  {encode_refused_sym}
+
             ",
             // Function & springboard symbols:
             ret_springboard_sym = sym og_tock_rv32i_c_rt_ret_springboard,
             encode_ret_sym = sym Self::encode_return,
+            encode_refused_sym = sym Self::encode_refused,
+            invoke_preempted_sym = sym Self::invoke_preempted,
             callback_handler = sym Self::callback_handler,
             // Runtime ASM state offsets:
             rtas_foreign_stack_ptr_offset = const core::mem::offset_of!(TockRv32iCRtAsmState, foreign_stack_ptr),
             rtas_foreign_stack_bottom_offset = const core::mem::offset_of!(TockRv32iCRtAsmState, foreign_stack_bottom),
+            rtas_faulted_offset = const core::mem::offset_of!(TockRv32iCRtAsmState, faulted),
+            rtas_suspended_offset = const core::mem::offset_of!(TockRv32iCRtAsmState, suspended),
+            rtas_servicing_interrupts_offset = const core::mem::offset_of!(TockRv32iCRtAsmState, servicing_interrupts),
+            rtas_suspended_regs_offset = const core::mem::offset_of!(TockRv32iCRtAsmState, suspended_regs),
+            rtas_suspendable_offset = const core::mem::offset_of!(TockRv32iCRtAsmState, suspendable),
             // Callback context + pointer stack frame size:
             callback_ctx_ptr_size = const CALLBACK_CONTEXT_PLUS_POINTER_STACKED_SIZE,
             callback_ctx_foreign_stack_ptr_offset = const core::mem::offset_of!(
@@ -1335,6 +3219,8 @@ impl<ID: OGID, M: MPU + 'static> TockRv32iCRt<ID, M> {
                 TockRv32iCRtCallbackAsmContext, ret_a0),
             callback_ctx_ret_a1_offset = const core::mem::offset_of!(
                 TockRv32iCRtCallbackAsmContext, ret_a1),
+            callback_ctx_ret_pc_offset = const core::mem::offset_of!(
+                TockRv32iCRtCallbackAsmContext, ret_pc),
         );
     }
 
@@ -1348,12 +3234,26 @@ impl<ID: OGID, M: MPU + 'static> TockRv32iCRt<ID, M> {
         a6_mtval: usize,
         a7_mepc: usize,
     ) {
-        // Determine whether the function faulted, returned to the kernel using
-        // a regular `ecall` instruction, or tried to return, or tried to issue
-        // a callback.
-        if a5_mcause == MCAUSE_ENV_CALL_UMODE
-            || (a5_mcause == MCAUSE_INSTRUCTION_ACCESS_FAULT
-                && a7_mepc == og_tock_rv32i_c_rt_ret_springboard as usize)
+        // Determine whether the function was suspended, faulted, was aborted
+        // for exceeding its execution-time budget, returned to the kernel
+        // using an `ecall` instruction at the return springboard, or tried to
+        // return, or tried to issue a callback.
+        if a2_rt.asm_state.suspended.get() {
+            // Function was preempted and its state saved in the runtime:
+            a3_invoke_res.error = TockRv32iCInvokeErr::Suspended;
+        } else if a2_rt.asm_state.faulted.get() {
+            // A nested invocation of this runtime, issued from a callback,
+            // faulted or was aborted. This aborts the current invocation as
+            // well. The fault has already been recorded and handled by the
+            // fault policy, so only report it:
+            a3_invoke_res.error = match a2_rt.fault.get() {
+                Some(fault) if fault.is_timeout() => TockRv32iCInvokeErr::Timeout(fault),
+                Some(fault) => TockRv32iCInvokeErr::Fault(fault),
+                None => TockRv32iCInvokeErr::RuntimeFaulted,
+            };
+        } else if (a5_mcause == MCAUSE_ENV_CALL_UMODE
+            || a5_mcause == MCAUSE_INSTRUCTION_ACCESS_FAULT)
+            && a7_mepc == og_tock_rv32i_c_rt_ret_springboard as usize
         {
             // Function returned "normally", so we encode that:
             a3_invoke_res.error = TockRv32iCInvokeErr::NoError;
@@ -1361,14 +3261,109 @@ impl<ID: OGID, M: MPU + 'static> TockRv32iCRt<ID, M> {
             a3_invoke_res.a1 = a1;
             a3_invoke_res.sp = a4_fsp;
         } else {
-            // TODO: encode proper error here!
-            panic!(
-                "Function faulted:\r\n\
-                 a0={:08x}, a1={:08x}, rt={:p}, invoke_res={:p},\r\n\
-                 fsp={:p}, mcause={:08x} mtval={:08x} mepc={:08x}",
-                a0, a1, a2_rt, a3_invoke_res, a4_fsp, a5_mcause, a6_mtval, a7_mepc,
-            );
+            // Function faulted or was aborted. Its memory is now in an
+            // undefined state, so we mark the runtime as faulted, refusing all
+            // further invocations:
+            let fault = TockRv32iCFault {
+                mcause: a5_mcause,
+                mtval: a6_mtval,
+                mepc: a7_mepc,
+                foreign_stack_ptr: a4_fsp,
+            };
+
+            a2_rt.asm_state.faulted.set(true);
+            a2_rt.fault.set(Some(fault));
+            a2_rt.fault_count.set(a2_rt.fault_count.get() + 1);
+
+            match a2_rt.fault_policy.action(a2_rt.restart_count.get()) {
+                OmniglotFaultAction::Panic => panic!(
+                    "Function faulted:\r\n\
+                     a0={:08x}, a1={:08x}, rt={:p}, invoke_res={:p},\r\n\
+                     fsp={:p}, mcause={:08x} mtval={:08x} mepc={:08x}",
+                    a0, a1, a2_rt, a3_invoke_res, a4_fsp, a5_mcause, a6_mtval, a7_mepc,
+                ),
+                OmniglotFaultAction::Stop => (),
+                OmniglotFaultAction::Restart => a2_rt.restart_pending.set(true),
+            }
+
+            a3_invoke_res.error = if fault.is_timeout() {
+                TockRv32iCInvokeErr::Timeout(fault)
+            } else {
+                TockRv32iCInvokeErr::Fault(fault)
+            };
+        }
+    }
+
+    extern "C" fn encode_refused(
+        a0_rt: &TockRv32iCRtAsmState,
+        a1_invoke_res: &mut TockRv32iCInvokeResInner,
+    ) {
+        a1_invoke_res.error = if a0_rt.faulted.get() {
+            TockRv32iCInvokeErr::RuntimeFaulted
+        } else {
+            TockRv32iCInvokeErr::RuntimeBusy
+        };
+    }
+
+    // Called on every interrupt taken while executing foreign code. Returns
+    // 0 to resume the foreign function, 1 to abort it, or 2 to suspend it.
+    extern "C" fn invoke_preempted(rt: &Self) -> usize {
+        let trap_cycle = Self::read_mcycle();
+
+        // Only consider the timer when it has been armed for this invocation:
+        let expired = rt.invoke_budget_armed.get()
+            && rt
+                .invoke_budget
+                .get()
+                .is_some_and(|(timer, _)| timer.get_remaining_us().is_none());
+
+        if expired {
+            // The interrupt is handled once we have returned to the kernel:
+            if rt.interrupt_deferred_since.get().is_none() {
+                rt.interrupt_deferred_since.set(Some(trap_cycle));
+            }
+
+            // Only the outermost invocation can be suspended. Nested
+            // invocations are aborted, which aborts the outermost one too:
+            return if rt.asm_state.suspendable.get() && rt.callback_depth.get() == 0 {
+                rt.asm_state.suspended.set(true);
+                2
+            } else {
+                1
+            };
+        }
+
+        match rt.interrupt_service.get() {
+            Some(service) => {
+                // The kernel is not restricted by the application MPU, but
+                // other runtimes may reconfigure it while we are servicing
+                // interrupts. Thus, restore our configuration afterwards:
+                rt.asm_state.servicing_interrupts.set(true);
+                rt.mpu.disable_app_mpu();
+                rt.record_interrupt_latency(trap_cycle);
+
+                // Deferred calls may invoke foreign functions of other
+                // runtimes, which overwrite `mepc` and return with
+                // interrupts enabled and `mstatus.MPP` set to machine mode.
+                // We resume the preempted function through `mret`, so restore
+                // the state of our trap context afterwards:
+                let trap_csrs = Self::read_trap_csrs();
+                service.service_interrupts();
+                unsafe { Self::restore_trap_csrs(trap_csrs) };
+
+                rt.rearm_invoke_budget();
+                rt.configure_mpu();
+                rt.mpu.enable_app_mpu();
+                rt.asm_state.servicing_interrupts.set(false);
+            }
+            None => {
+                if rt.interrupt_deferred_since.get().is_none() {
+                    rt.interrupt_deferred_since.set(Some(trap_cycle));
+                }
+            }
         }
+
+        0
     }
 
     fn setup_callback_int<'a, C, F, R>(
@@ -1475,37 +3470,50 @@ unsafe impl<ID: OGID, M: MPU + 'static> OGRuntime for TockRv32iCRt<ID, M> {
     type CallbackContext = TockRv32iCRtCallbackContext;
     type CallbackReturn = TockRv32iCRtCallbackReturn;
 
-    // We don't have any symbol table state, as the Tock OG binary
-    // already contains a symbol table that we can use.
-    type SymbolTableState<const SYMTAB_SIZE: usize, const FIXED_OFFSET_SYMTAB_SIZE: usize> = ();
+    // The Tock OG binary already contains a symbol table that we can use. We
+    // record the function table index of every symbol in the compact symbol
+    // table, as verified against the binary's function names:
+    type SymbolTableState<const SYMTAB_SIZE: usize, const FIXED_OFFSET_SYMTAB_SIZE: usize> =
+        [usize; SYMTAB_SIZE];
 
     fn resolve_symbols<const SYMTAB_SIZE: usize, const FIXED_OFFSET_SYMTAB_SIZE: usize>(
         &self,
-        _symbol_table: &'static [&'static CStr; SYMTAB_SIZE],
+        symbol_table: &'static [&'static CStr; SYMTAB_SIZE],
         fixed_offset_symbol_table: &'static [Option<&'static CStr>; FIXED_OFFSET_SYMTAB_SIZE],
     ) -> Option<Self::SymbolTableState<SYMTAB_SIZE, FIXED_OFFSET_SYMTAB_SIZE>> {
         // Check whether the binary's symbol table is large enough to contain
         // all symbols that could possbily be referenced by the fixed offset
         // symbol table (i.e., binary symtab size >= FIXED_OFFSET_SYMTAB_SIZE),
-        // and that all symbols are contained in the binary (TODO!).
+        // and that each of them has the expected name:
         if fixed_offset_symbol_table.len() > self.fntab_length {
-            None
-        } else {
-            Some(())
+            return None;
+        }
+
+        for (idx, symbol) in fixed_offset_symbol_table.iter().enumerate() {
+            if let Some(name) = symbol {
+                if !self.fntab_name_matches(idx, name) {
+                    return None;
+                }
+            }
+        }
+
+        // Resolve all symbols of the compact symbol table by name:
+        let mut symtabstate = [0; SYMTAB_SIZE];
+        for (resolved, name) in symtabstate.iter_mut().zip(symbol_table.iter()) {
+            *resolved = (0..self.fntab_length).find(|&idx| self.fntab_name_matches(idx, name))?;
         }
+
+        Some(symtabstate)
     }
 
     fn lookup_symbol<const SYMTAB_SIZE: usize, const FIXED_OFFSET_SYMTAB_SIZE: usize>(
         &self,
-        _compact_symtab_index: usize,
-        fixed_offset_symtab_index: usize,
-        _symtabstate: &Self::SymbolTableState<SYMTAB_SIZE, FIXED_OFFSET_SYMTAB_SIZE>,
+        compact_symtab_index: usize,
+        _fixed_offset_symtab_index: usize,
+        symtabstate: &Self::SymbolTableState<SYMTAB_SIZE, FIXED_OFFSET_SYMTAB_SIZE>,
     ) -> Option<*const ()> {
-        if fixed_offset_symtab_index < self.fntab_length {
-            Some(unsafe { *(self.fntab_addr as *const *const ()).add(fixed_offset_symtab_index) })
-        } else {
-            None
-        }
+        let fntab_index = *symtabstate.get(compact_symtab_index)?;
+        Some(unsafe { *(self.fntab_addr as *const *const ()).add(fntab_index) })
     }
 
     fn setup_callback<'a, C, F, R>(
@@ -1562,7 +3570,32 @@ unsafe impl<ID: OGID, M: MPU + 'static> OGRuntime for TockRv32iCRt<ID, M> {
             .set(alloc_scope as *mut _ as *mut ());
         // panic!("Other context: {:?}", self.asm_state.active_alloc_scope.get());
 
-        let res = self.execute_int_configure_mpu(f);
+        // Restarting the library is only safe outside of any foreign
+        // invocation, i.e., not from within a callback. The execution-time
+        // budget, if any, applies to the outermost `execute` only:
+        let res = if prev_active_alloc_scope.is_null() {
+            self.restart_if_pending();
+
+            // Callbacks and grants are released when this `execute` returns,
+            // so an invocation using them must not be suspended:
+            self.asm_state
+                .suspendable
+                .set(self.split_phase.get() && !alloc_scope.tracker().has_scoped_entries());
+
+            let res = self.with_invoke_budget(|| self.execute_int_configure_mpu(f));
+            self.asm_state.suspendable.set(false);
+            res
+        } else {
+            self.execute_int_configure_mpu(f)
+        };
+
+        // Interrupts deferred during this invocation can be handled once we
+        // return from the outermost `execute`:
+        if prev_active_alloc_scope.is_null() {
+            if let Some(since) = self.interrupt_deferred_since.take() {
+                self.record_interrupt_latency(since);
+            }
+        }
 
         // Restore the previous alloc scope:
         self.asm_state
@@ -1583,6 +3616,12 @@ unsafe impl<ID: OGID, M: MPU + 'static> OGRuntime for TockRv32iCRt<ID, M> {
     where
         F: FnOnce(*mut ()) -> R,
     {
+        // A suspended invocation still uses the foreign stack below the
+        // foreign stack pointer:
+        if self.is_suspended() {
+            return Err(OGError::AllocNoMem);
+        }
+
         let mut fsp = self.asm_state.foreign_stack_ptr.get() as usize;
         let original_fsp = fsp;
 
@@ -1760,3 +3799,86 @@ core::arch::global_asm!(
         ecall
     "
 );
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+
+    use omniglot::alloc_tracker::AllocTracker;
+
+    use super::{
+        persistent_pool_first_fit, persistent_pool_mark_blocks, TockRv32iCRtAllocChain,
+        TockRv32iCRtAllocations, PERSISTENT_POOL_BITMAP_WORDS,
+    };
+
+    fn bitmap() -> [Cell<usize>; PERSISTENT_POOL_BITMAP_WORDS] {
+        core::array::from_fn(|_| Cell::new(0))
+    }
+
+    #[test]
+    fn first_fit_empty_pool() {
+        let bitmap = bitmap();
+        assert_eq!(persistent_pool_first_fit(&bitmap, 0, 1), None);
+        assert_eq!(persistent_pool_first_fit(&bitmap, 8, 1), Some(0));
+        assert_eq!(persistent_pool_first_fit(&bitmap, 8, 8), Some(0));
+        assert_eq!(persistent_pool_first_fit(&bitmap, 8, 9), None);
+    }
+
+    #[test]
+    fn first_fit_skips_allocated_runs() {
+        let bitmap = bitmap();
+        persistent_pool_mark_blocks(&bitmap, 0, 2, true);
+        persistent_pool_mark_blocks(&bitmap, 3, 1, true);
+
+        // The single free block between both allocations fits exactly:
+        assert_eq!(persistent_pool_first_fit(&bitmap, 8, 1), Some(2));
+        assert_eq!(persistent_pool_first_fit(&bitmap, 8, 2), Some(4));
+        assert_eq!(persistent_pool_first_fit(&bitmap, 8, 4), Some(4));
+        assert_eq!(persistent_pool_first_fit(&bitmap, 8, 5), None);
+
+        persistent_pool_mark_blocks(&bitmap, 0, 2, false);
+        assert_eq!(persistent_pool_first_fit(&bitmap, 8, 3), Some(0));
+    }
+
+    #[test]
+    fn first_fit_across_bitmap_words() {
+        let bits = usize::BITS as usize;
+        let bitmap = bitmap();
+        persistent_pool_mark_blocks(&bitmap, 0, bits - 1, true);
+
+        assert_eq!(bitmap[0].get(), usize::MAX >> 1);
+        assert_eq!(bitmap[1].get(), 0);
+        assert_eq!(
+            persistent_pool_first_fit(&bitmap, 2 * bits, 3),
+            Some(bits - 1)
+        );
+
+        persistent_pool_mark_blocks(&bitmap, bits - 1, 3, true);
+        assert_eq!(bitmap[0].get(), usize::MAX);
+        assert_eq!(bitmap[1].get(), 0b11);
+    }
+
+    #[test]
+    fn persistent_pool_only_valid_through_chain_entry() {
+        let base = TockRv32iCRtAllocChain::BaseAllocations(TockRv32iCRtAllocations {
+            ram_region_start: 0x1000 as *mut (),
+            ram_region_length: 0x1000,
+            persistent_pool_length: 0x100,
+            flash_region_start: 0x8000 as *mut (),
+            flash_region_length: 0x1000,
+        });
+
+        assert!(base.is_valid_mut(0x1000 as *mut (), 0xf00));
+        assert!(!base.is_valid_mut(0x1f00 as *mut (), 4));
+        assert!(!base.is_valid_mut(0x1efc as *mut (), 8));
+        assert!(base.is_valid(0x8000 as *const (), 0x1000));
+        assert!(!base.is_valid_mut(0x8000 as *mut (), 4));
+
+        let persistent =
+            TockRv32iCRtAllocChain::PersistentAllocation(0x1f10 as *mut (), 0x20, &base);
+        assert!(persistent.is_valid_mut(0x1f10 as *mut (), 0x20));
+        assert!(persistent.is_valid(0x1f20 as *const (), 4));
+        assert!(!persistent.is_valid_mut(0x1f00 as *mut (), 4));
+        assert!(!persistent.is_valid_mut(0x1f20 as *mut (), 0x20));
+    }
+}
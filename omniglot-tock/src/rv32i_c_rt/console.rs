@@ -0,0 +1,156 @@
+//! Console output of foreign libraries, provided as a host call.
+//!
+//! The C runtime's `_write` implementation issues the `HOST_CALL_CONSOLE_WRITE`
+//! host call for `stdout` and `stderr`. This service copies the written bytes
+//! out of foreign memory, buffers them per line, and prints each line to the
+//! kernel debug console, prefixed with the library's name. Output exceeding a
+//! per-second byte budget is dropped.
+
+use core::cell::Cell;
+use core::fmt;
+
+use kernel::hil::time::{ConvertTicks, Ticks, Time};
+use kernel::utilities::cells::MapCell;
+
+use omniglot::alloc_tracker::AllocTracker;
+
+use super::host_call::TockRv32iCHostCallService;
+use super::TockRv32iCRtAllocChain;
+
+/// Host call number of the console write service, called with arguments
+/// `(fd, buf, len)`. Returns the number of bytes consumed in `a0`.
+pub const HOST_CALL_CONSOLE_WRITE: usize = 1;
+
+const LINE_BUFFER_LEN: usize = 128;
+
+// Displays a byte string, replacing invalid UTF-8 sequences:
+struct LossyStr<'a>(&'a [u8]);
+
+impl fmt::Display for LossyStr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for chunk in self.0.utf8_chunks() {
+            f.write_str(chunk.valid())?;
+            if !chunk.invalid().is_empty() {
+                f.write_str("\u{FFFD}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub struct TockRv32iCConsoleService<'a, T: Time> {
+    name: &'static str,
+    time: &'a T,
+    bytes_per_second: usize,
+
+    line: MapCell<([u8; LINE_BUFFER_LEN], usize)>,
+
+    // Start of the current rate-limiting window, the number of bytes
+    // accepted within it, and the number of bytes dropped since the last
+    // report:
+    window_start: Cell<Option<T::Ticks>>,
+    window_bytes: Cell<usize>,
+    dropped_bytes: Cell<usize>,
+}
+
+impl<'a, T: Time> TockRv32iCConsoleService<'a, T> {
+    pub fn new(name: &'static str, time: &'a T, bytes_per_second: usize) -> Self {
+        TockRv32iCConsoleService {
+            name,
+            time,
+            bytes_per_second,
+            line: MapCell::new(([0; LINE_BUFFER_LEN], 0)),
+            window_start: Cell::new(None),
+            window_bytes: Cell::new(0),
+            dropped_bytes: Cell::new(0),
+        }
+    }
+
+    // Returns how many of `len` bytes may be printed in the current window:
+    fn rate_limit(&self, len: usize) -> usize {
+        let now = self.time.now();
+        let window_expired = self
+            .window_start
+            .get()
+            .is_none_or(|start| now.wrapping_sub(start) >= self.time.ticks_from_ms(1000));
+
+        if window_expired {
+            self.window_start.set(Some(now));
+            self.window_bytes.set(0);
+
+            let dropped = self.dropped_bytes.replace(0);
+            if dropped != 0 {
+                kernel::debug!("[{}] <{} bytes dropped>", self.name, dropped);
+            }
+        }
+
+        let accepted = core::cmp::min(
+            len,
+            self.bytes_per_second
+                .saturating_sub(self.window_bytes.get()),
+        );
+        self.window_bytes.set(self.window_bytes.get() + accepted);
+        self.dropped_bytes
+            .set(self.dropped_bytes.get() + (len - accepted));
+
+        accepted
+    }
+
+    fn flush(&self, buf: &mut [u8; LINE_BUFFER_LEN], len: &mut usize) {
+        kernel::debug!("[{}] {}", self.name, LossyStr(&buf[..*len]));
+        *len = 0;
+    }
+
+    fn write(&self, bytes: impl Iterator<Item = u8>) {
+        self.line.map(|(buf, len)| {
+            for byte in bytes {
+                if byte == b'\n' {
+                    self.flush(buf, len);
+                    continue;
+                }
+
+                buf[*len] = byte;
+                *len += 1;
+
+                if *len == LINE_BUFFER_LEN {
+                    self.flush(buf, len);
+                }
+            }
+        });
+    }
+}
+
+impl<T: Time> TockRv32iCHostCallService for TockRv32iCConsoleService<'_, T> {
+    fn host_call(
+        &self,
+        _num: usize,
+        args: [usize; 6],
+        alloc_tracker: &TockRv32iCRtAllocChain<'_>,
+    ) -> (usize, usize) {
+        let [fd, buf, len, ..] = args;
+
+        // Only stdout and stderr are routed to the console:
+        if fd != 1 && fd != 2 {
+            return (-9_isize as usize, 0); // -EBADF
+        }
+
+        // Validate the entire buffer before reading any of it:
+        if !alloc_tracker.is_valid(buf as *const (), len) {
+            return (-14_isize as usize, 0); // -EFAULT
+        }
+
+        // Report all bytes as written, even when dropping some, as callers
+        // would otherwise retry indefinitely:
+        let accepted = self.rate_limit(len);
+
+        // The foreign function is stopped while we handle its host call, so
+        // this memory cannot be modified concurrently. We copy it out byte by
+        // byte, never creating a Rust reference to foreign memory:
+        self.write(
+            (0..accepted)
+                .map(|offset| unsafe { core::ptr::read_volatile((buf as *const u8).add(offset)) }),
+        );
+
+        (len, 0)
+    }
+}
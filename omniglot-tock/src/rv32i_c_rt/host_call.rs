@@ -0,0 +1,33 @@
+//! Numbered host calls from foreign code into kernel services.
+//!
+//! Foreign code issues a host call through an `ecall` instruction anywhere
+//! but the runtime's return springboard. The host-call number is passed in
+//! `a7`, up to six arguments in `a0` -- `a5`, and results are returned in
+//! `a0` and `a1`. Execution then resumes after the `ecall` instruction. The
+//! C runtime provides the `omniglot_hostcall` function for this purpose (see
+//! `omniglot_c_rt/omniglot_hostcall.h`).
+
+use super::TockRv32iCRtAllocChain;
+
+/// Returned in `a0` when a host call number is not provided by a runtime.
+pub const HOST_CALL_ERR_NOSYS: usize = -38_isize as usize;
+
+/// A kernel service that foreign code can call into.
+///
+/// Services are registered with a runtime as entries of a dispatch table,
+/// through `TockRv32iCRt::set_host_calls`. They run in the kernel, in the
+/// context of the runtime's trap handler, while the foreign function that
+/// issued the host call is stopped.
+pub trait TockRv32iCHostCallService {
+    /// Handle host call `num` with arguments `args`, returning the values of
+    /// `a0` and `a1`.
+    ///
+    /// Pointers passed by foreign code must be validated through
+    /// `alloc_tracker` before they are accessed.
+    fn host_call(
+        &self,
+        num: usize,
+        args: [usize; 6],
+        alloc_tracker: &TockRv32iCRtAllocChain<'_>,
+    ) -> (usize, usize);
+}
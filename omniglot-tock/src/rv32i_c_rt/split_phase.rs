@@ -0,0 +1,159 @@
+//! Split-phase foreign invocations, resumed from Tock deferred calls.
+//!
+//! This driver allows long-running foreign functions to be suspended when
+//! they exceed their execution-time budget, and to be continued later
+//! without blocking other kernel work. Completion of an invocation is
+//! reported through a client callback, like with other split-phase HILs.
+//!
+//! Only invocations that pass all arguments in registers, that return their
+//! result in registers, and that do not set up callbacks or grants, can be
+//! suspended. All others are aborted when exceeding their budget (see
+//! `TockRv32iCRt::set_split_phase`).
+
+use core::cell::Cell;
+
+use kernel::deferred_call::{DeferredCall, DeferredCallClient};
+use kernel::platform::mpu::MPU;
+use kernel::utilities::cells::{OptionalCell, TakeCell};
+use kernel::ErrorCode;
+
+use omniglot::foreign_memory::og_copy::OGCopy;
+use omniglot::id::OGID;
+use omniglot::markers::{AccessScope, AllocScope};
+use omniglot::{OGError, OGResult};
+
+use super::{TockRv32iCRt, TockRv32iCRtAllocChain};
+
+/// Client notified on completion of a split-phase invocation.
+pub trait TockRv32iCRtSplitPhaseClient<T> {
+    /// Called when a foreign invocation has completed, with its return value,
+    /// or an error if it faulted or timed out.
+    fn invoke_done(&self, result: OGResult<OGCopy<T>>);
+}
+
+/// Split-phase driver for invocations of foreign functions returning `T`,
+/// which must fit into the two return registers.
+pub struct TockRv32iCRtSplitPhase<'a, ID: OGID + 'static, M: MPU + 'static, T: 'static> {
+    rt: &'a TockRv32iCRt<ID, M>,
+    alloc_scope: TakeCell<'static, AllocScope<'static, TockRv32iCRtAllocChain<'static>, ID>>,
+    access_scope: TakeCell<'static, AccessScope<ID>>,
+    deferred_call: DeferredCall,
+    client: OptionalCell<&'a dyn TockRv32iCRtSplitPhaseClient<T>>,
+
+    // Set while an invocation is in progress, until its completion has been
+    // reported to the client:
+    busy: Cell<bool>,
+
+    // Result of a completed invocation, to be reported from a deferred call:
+    result: Cell<Option<OGResult<OGCopy<T>>>>,
+}
+
+impl<'a, ID: OGID + 'static, M: MPU + 'static, T: 'static> TockRv32iCRtSplitPhase<'a, ID, M, T> {
+    /// Create a new split-phase driver for a runtime, enabling split-phase
+    /// invocations on it.
+    ///
+    /// The runtime should have an invocation budget configured (through
+    /// `set_invoke_budget`), otherwise invocations are never suspended.
+    pub fn new(
+        rt: &'a TockRv32iCRt<ID, M>,
+        alloc_scope: &'static mut AllocScope<'static, TockRv32iCRtAllocChain<'static>, ID>,
+        access_scope: &'static mut AccessScope<ID>,
+    ) -> Self {
+        rt.set_split_phase(true);
+
+        TockRv32iCRtSplitPhase {
+            rt,
+            alloc_scope: TakeCell::new(alloc_scope),
+            access_scope: TakeCell::new(access_scope),
+            deferred_call: DeferredCall::new(),
+            client: OptionalCell::empty(),
+            busy: Cell::new(false),
+            result: Cell::new(None),
+        }
+    }
+
+    pub fn set_client(&self, client: &'a dyn TockRv32iCRtSplitPhaseClient<T>) {
+        self.client.set(client);
+    }
+
+    /// Start a foreign invocation.
+    ///
+    /// The closure `f` is called with the runtime and its scopes, and should
+    /// invoke a single foreign function, returning its result. If the
+    /// invocation is suspended, the error returned by `f` is discarded and
+    /// the invocation is resumed from deferred calls until it completes. In
+    /// either case, the client is notified of its completion from a deferred
+    /// call.
+    ///
+    /// Returns `BUSY` if another invocation is in progress.
+    pub fn invoke<F>(&self, f: F) -> Result<(), ErrorCode>
+    where
+        F: FnOnce(
+            &TockRv32iCRt<ID, M>,
+            &mut AllocScope<'static, TockRv32iCRtAllocChain<'static>, ID>,
+            &mut AccessScope<ID>,
+        ) -> OGResult<OGCopy<T>>,
+    {
+        if self.busy.get() || self.rt.is_suspended() {
+            return Err(ErrorCode::BUSY);
+        }
+
+        let (Some(alloc_scope), Some(access_scope)) =
+            (self.alloc_scope.take(), self.access_scope.take())
+        else {
+            return Err(ErrorCode::BUSY);
+        };
+
+        let res = f(self.rt, alloc_scope, access_scope);
+
+        self.alloc_scope.replace(alloc_scope);
+        self.access_scope.replace(access_scope);
+
+        if !self.rt.is_suspended() {
+            self.result.set(Some(res));
+        }
+
+        self.busy.set(true);
+        self.deferred_call.set();
+
+        Ok(())
+    }
+}
+
+impl<'a, ID: OGID + 'static, M: MPU + 'static, T: 'static> DeferredCallClient
+    for TockRv32iCRtSplitPhase<'a, ID, M, T>
+{
+    fn handle_deferred_call(&self) {
+        let res = match self.result.take() {
+            Some(res) => res,
+            None => {
+                // Continue the suspended invocation:
+                let resumed = self
+                    .alloc_scope
+                    .map(|alloc_scope| {
+                        self.access_scope
+                            .map(|access_scope| self.rt.resume(alloc_scope, access_scope))
+                    })
+                    .flatten();
+
+                match resumed {
+                    // Suspended again, continue from the next deferred call:
+                    Some(Ok(None)) => {
+                        self.deferred_call.set();
+                        return;
+                    }
+                    Some(Ok(Some(ret))) => Ok(ret),
+                    Some(Err(e)) => Err(e),
+                    None => Err(OGError::InternalError),
+                }
+            }
+        };
+
+        self.busy.set(false);
+        self.client.map(|client| client.invoke_done(res));
+    }
+
+    fn register(&'static self) {
+        self.deferred_call.register(self);
+    }
+}
@@ -0,0 +1,201 @@
+//! Delivery of asynchronous kernel events into a foreign library.
+//!
+//! Capsules queue events with `TockRv32iCUpcallQueue::enqueue`. These are
+//! delivered in order, one per deferred call, to a handler function exported
+//! by the library, whenever the runtime is idle. The handler is called as
+//!
+//! ```c
+//! void handler(uint32_t event, uint32_t arg0, uint32_t arg1);
+//! ```
+
+use core::cell::Cell;
+
+use kernel::collections::queue::Queue;
+use kernel::collections::ring_buffer::RingBuffer;
+use kernel::deferred_call::{DeferredCall, DeferredCallClient};
+use kernel::platform::mpu::MPU;
+use kernel::utilities::cells::{MapCell, TakeCell};
+use kernel::ErrorCode;
+
+use omniglot::abi::calling_convention::AREG3;
+use omniglot::abi::rv32i_c::Rv32iCABI;
+use omniglot::id::OGID;
+use omniglot::markers::{AccessScope, AllocScope};
+use omniglot::rt::rv32i_c::{Rv32iCInvokeRes, Rv32iCRt};
+use omniglot::rt::OGRuntime;
+
+use super::{TockRv32iCInvokeErr, TockRv32iCInvokeRes, TockRv32iCRt, TockRv32iCRtAllocChain};
+
+/// An event to be delivered to a foreign library.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TockRv32iCUpcall {
+    pub event: usize,
+    pub arg0: usize,
+    pub arg1: usize,
+}
+
+pub struct TockRv32iCUpcallQueue<'a, ID: OGID + 'static, M: MPU + 'static> {
+    rt: &'a TockRv32iCRt<ID, M>,
+    alloc_scope: TakeCell<'static, AllocScope<'static, TockRv32iCRtAllocChain<'static>, ID>>,
+    access_scope: TakeCell<'static, AccessScope<ID>>,
+    queue: MapCell<RingBuffer<'static, TockRv32iCUpcall>>,
+    handler: Cell<Option<*const ()>>,
+    deferred_call: DeferredCall,
+
+    // Set while a delivered upcall is suspended (see `split_phase`), such
+    // that we resume it before delivering any further upcalls:
+    delivering: Cell<bool>,
+
+    // Number of events dropped, either because the queue was full or
+    // because the library had faulted:
+    dropped: Cell<usize>,
+}
+
+impl<'a, ID: OGID + 'static, M: MPU + 'static> TockRv32iCUpcallQueue<'a, ID, M> {
+    pub fn new(
+        rt: &'a TockRv32iCRt<ID, M>,
+        alloc_scope: &'static mut AllocScope<'static, TockRv32iCRtAllocChain<'static>, ID>,
+        access_scope: &'static mut AccessScope<ID>,
+        buffer: &'static mut [TockRv32iCUpcall],
+    ) -> Self {
+        TockRv32iCUpcallQueue {
+            rt,
+            alloc_scope: TakeCell::new(alloc_scope),
+            access_scope: TakeCell::new(access_scope),
+            queue: MapCell::new(RingBuffer::new(buffer)),
+            handler: Cell::new(None),
+            deferred_call: DeferredCall::new(),
+            delivering: Cell::new(false),
+            dropped: Cell::new(0),
+        }
+    }
+
+    /// Set the foreign handler function that events are delivered to, for
+    /// instance as returned by a registration function of the library.
+    ///
+    /// Returns `INVAL` if `handler` does not point into the library's binary.
+    pub fn set_handler(&self, handler: *const ()) -> Result<(), ErrorCode> {
+        let valid = self
+            .alloc_scope
+            .map(|alloc_scope| {
+                alloc_scope
+                    .tracker()
+                    .get_base_allocations()
+                    .is_valid_fnptr(handler)
+            })
+            .ok_or(ErrorCode::BUSY)?;
+
+        if !valid {
+            return Err(ErrorCode::INVAL);
+        }
+
+        self.handler.set(Some(handler));
+        Ok(())
+    }
+
+    /// Queue an event for delivery. Returns `NOMEM` if the queue is full.
+    pub fn enqueue(&self, upcall: TockRv32iCUpcall) -> Result<(), ErrorCode> {
+        let enqueued = self
+            .queue
+            .map(|queue| queue.enqueue(upcall))
+            .unwrap_or(false);
+
+        if !enqueued {
+            self.dropped.set(self.dropped.get() + 1);
+            return Err(ErrorCode::NOMEM);
+        }
+
+        self.deferred_call.set();
+        Ok(())
+    }
+
+    pub fn dropped_count(&self) -> usize {
+        self.dropped.get()
+    }
+
+    fn deliver(&self, upcall: TockRv32iCUpcall, handler: *const ()) -> TockRv32iCInvokeErr {
+        let mut res: TockRv32iCInvokeRes<TockRv32iCRt<ID, M>, ()> = TockRv32iCInvokeRes::new();
+
+        let executed = self.alloc_scope.map(|alloc_scope| {
+            self.access_scope.map(|access_scope| {
+                self.rt.execute(alloc_scope, access_scope, || unsafe {
+                    let invoke: unsafe extern "C" fn(
+                        usize,
+                        usize,
+                        usize,
+                        *const TockRv32iCRt<ID, M>,
+                        *const (),
+                        *mut TockRv32iCInvokeRes<TockRv32iCRt<ID, M>, ()>,
+                    ) = core::mem::transmute(
+                        <TockRv32iCRt<ID, M> as Rv32iCRt<0, AREG3<Rv32iCABI>>>::invoke
+                            as unsafe extern "C" fn(),
+                    );
+
+                    invoke(
+                        upcall.event,
+                        upcall.arg0,
+                        upcall.arg1,
+                        self.rt as *const _,
+                        handler,
+                        &mut res as *mut _,
+                    )
+                })
+            })
+        });
+
+        if executed.flatten().is_none() {
+            return TockRv32iCInvokeErr::NotCalled;
+        }
+
+        res.inner.error
+    }
+}
+
+impl<ID: OGID + 'static, M: MPU + 'static> DeferredCallClient for TockRv32iCUpcallQueue<'_, ID, M> {
+    fn handle_deferred_call(&self) {
+        if self.delivering.get() && self.rt.is_suspended() {
+            // Continue delivering the current upcall first:
+            let _ = self.alloc_scope.map(|alloc_scope| {
+                self.access_scope
+                    .map(|access_scope| self.rt.resume::<()>(alloc_scope, access_scope))
+            });
+            self.deferred_call.set();
+            return;
+        }
+        self.delivering.set(false);
+
+        // Wait for any other invocation to complete. We preserve the order of
+        // events, and thus only dequeue an event once it can be delivered:
+        if !self.rt.is_idle() {
+            self.deferred_call.set();
+            return;
+        }
+
+        let Some(upcall) = self.queue.map(|queue| queue.dequeue()).flatten() else {
+            return;
+        };
+
+        let Some(handler) = self.handler.get() else {
+            // No handler registered yet, the event is lost:
+            self.dropped.set(self.dropped.get() + 1);
+            return;
+        };
+
+        match self.deliver(upcall, handler) {
+            TockRv32iCInvokeErr::Suspended => self.delivering.set(true),
+            TockRv32iCInvokeErr::RuntimeFaulted | TockRv32iCInvokeErr::NotCalled => {
+                self.dropped.set(self.dropped.get() + 1);
+            }
+            // Faults of the handler are handled by the runtime's fault policy:
+            _ => (),
+        }
+
+        if self.delivering.get() || self.queue.map(|queue| queue.has_elements()) == Some(true) {
+            self.deferred_call.set();
+        }
+    }
+
+    fn register(&'static self) {
+        self.deferred_call.register(self);
+    }
+}